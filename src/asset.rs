@@ -0,0 +1,68 @@
+use genpdf::style;
+use serde::Deserialize;
+
+use crate::receita::{AssetClass, BensCode};
+use crate::tax_year::TaxYear;
+use crate::theme::Rgb;
+
+/// Static information about a crypto asset, used to label and color its Bens e Direitos section.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetDescriptor {
+    pub ticker: String,
+    pub name: String,
+    /// Decides the Bens e Direitos code the asset is declared under.
+    pub class: AssetClass,
+    /// Accent color as `[r, g, b]`.
+    pub color: Rgb,
+    /// Number of decimal places the asset can be divided into.
+    pub decimals: u8,
+}
+
+impl AssetDescriptor {
+    fn new(ticker: &str, name: &str, class: AssetClass, color: [u8; 3], decimals: u8) -> Self {
+        Self {
+            ticker: ticker.to_string(),
+            name: name.to_string(),
+            class,
+            color: Rgb(color),
+            decimals,
+        }
+    }
+
+    /// Looks up one of the assets we custody out of the box.
+    pub fn builtin(ticker: &str) -> Option<Self> {
+        use AssetClass::*;
+        let descriptor = match ticker {
+            "BTC" => Self::new("BTC", "Bitcoin", Bitcoin, [68, 87, 212], 8),
+            "ETH" => Self::new("ETH", "Ethereum", Altcoin, [98, 126, 234], 18),
+            "SOL" => Self::new("SOL", "Solana", Altcoin, [153, 69, 255], 9),
+            "USDC" => Self::new("USDC", "USD Coin", Stablecoin, [39, 117, 202], 6),
+            "PAXG" => Self::new("PAXG", "PAX Gold", Stablecoin, [255, 204, 0], 18),
+            _ => return None,
+        };
+        Some(descriptor)
+    }
+
+    pub fn accent(&self) -> style::Color {
+        self.color.into()
+    }
+
+    pub fn bens_code(&self, tax_year: TaxYear) -> BensCode {
+        BensCode::new(self.class, tax_year)
+    }
+
+    /// Section heading, e.g. "Grupo 08 - Código 01 - Criptoativo Bitcoin (Bitcoin - BTC)", or
+    /// "81 - Criptoativo Bitcoin (Bitcoin - BTC)" before Grupo 08.
+    pub fn heading(&self, tax_year: TaxYear) -> String {
+        let code = self.bens_code(tax_year);
+        let code_label = match code.group {
+            Some(group) => format!("Grupo {:02} - Código {:02}", group, code.code),
+            None => code.code.to_string(),
+        };
+        format!(
+            "{} - {} ({} - {})",
+            code_label, code.label, self.name, self.ticker
+        )
+    }
+}
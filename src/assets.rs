@@ -0,0 +1,71 @@
+//! Fonts and logo the report is rendered with. With the `embedded-assets` feature they are
+//! compiled into the binary, so rendering doesn't depend on the working directory; otherwise
+//! they are read from `assets/` relative to it.
+
+use std::path::Path;
+
+use genpdf::elements::Image;
+use genpdf::fonts::{self, FontData, FontFamily};
+
+use crate::error::Error;
+
+const FONT_FAMILY: &str = "Roboto";
+
+#[cfg(not(feature = "embedded-assets"))]
+const FONTS_DIR: &str = "assets/Roboto";
+#[cfg(not(feature = "embedded-assets"))]
+const LOGO: &str = "assets/bipa-logo.jpg";
+
+/// Loads the Roboto family from `dir`, or the default copy if `dir` is `None`.
+pub fn load_fonts(dir: Option<&Path>) -> Result<FontFamily<FontData>, Error> {
+    match dir {
+        Some(dir) => {
+            fonts::from_files(dir, FONT_FAMILY, None).map_err(|source| Error::FontMissing {
+                dir: Some(dir.to_path_buf()),
+                source,
+            })
+        }
+        None => default_fonts(),
+    }
+}
+
+/// Loads the logo at `path`, or the default one if `path` is `None`.
+pub fn load_logo(path: Option<&Path>) -> Result<Image, Error> {
+    match path {
+        Some(path) => Image::from_path(path).map_err(|source| Error::ImageUnreadable {
+            path: Some(path.to_path_buf()),
+            source,
+        }),
+        None => default_logo(),
+    }
+}
+
+#[cfg(feature = "embedded-assets")]
+fn default_fonts() -> Result<FontFamily<FontData>, Error> {
+    let font = |bytes: &[u8]| {
+        FontData::new(bytes.to_vec(), None)
+            .map_err(|source| Error::FontMissing { dir: None, source })
+    };
+    Ok(FontFamily {
+        regular: font(include_bytes!("../assets/Roboto/Roboto-Regular.ttf"))?,
+        bold: font(include_bytes!("../assets/Roboto/Roboto-Bold.ttf"))?,
+        italic: font(include_bytes!("../assets/Roboto/Roboto-Italic.ttf"))?,
+        bold_italic: font(include_bytes!("../assets/Roboto/Roboto-BoldItalic.ttf"))?,
+    })
+}
+
+#[cfg(not(feature = "embedded-assets"))]
+fn default_fonts() -> Result<FontFamily<FontData>, Error> {
+    load_fonts(Some(Path::new(FONTS_DIR)))
+}
+
+#[cfg(feature = "embedded-assets")]
+fn default_logo() -> Result<Image, Error> {
+    let bytes = std::io::Cursor::new(include_bytes!("../assets/bipa-logo.jpg"));
+    Image::from_reader(bytes).map_err(|source| Error::ImageUnreadable { path: None, source })
+}
+
+#[cfg(not(feature = "embedded-assets"))]
+fn default_logo() -> Result<Image, Error> {
+    load_logo(Some(Path::new(LOGO)))
+}
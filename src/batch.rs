@@ -0,0 +1,221 @@
+//! Renders many reports across a pool of worker threads sharing one [`Renderer`], for the yearly
+//! run that issues an Informe to every customer.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::Error;
+use crate::input::{InputError, Loaded};
+use crate::model::Report;
+use crate::render::Renderer;
+
+type Load = Box<dyn FnOnce() -> Result<Loaded, InputError> + Send>;
+
+/// One report of a batch: how to load its input and where to write its PDF. Inputs are loaded on
+/// the worker threads too, so that reading and decoding them doesn't hold up the pool.
+pub struct Job {
+    pub name: String,
+    pub output: PathBuf,
+    load: Load,
+}
+
+impl Job {
+    pub fn new(
+        name: impl Into<String>,
+        output: impl Into<PathBuf>,
+        load: impl FnOnce() -> Result<Loaded, InputError> + Send + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            output: output.into(),
+            load: Box::new(load),
+        }
+    }
+}
+
+/// A PDF written by a [`Job`].
+pub struct Rendered {
+    pub pages: usize,
+    /// Hex-encoded SHA-256 of the file.
+    pub sha256: String,
+}
+
+/// What became of one [`Job`].
+pub struct Outcome {
+    pub name: String,
+    pub output: PathBuf,
+    /// Problems the input's [`crate::input::TaxIdPolicy`] let through.
+    pub warnings: Vec<InputError>,
+    /// Time spent loading and rendering the job.
+    pub duration: Duration,
+    pub result: Result<Rendered, Error>,
+}
+
+/// Every [`Outcome`] of a batch, in the order the jobs were given.
+pub struct Summary {
+    pub outcomes: Vec<Outcome>,
+    /// Wall-clock time of the whole batch.
+    pub elapsed: Duration,
+}
+
+impl Summary {
+    pub fn rendered(&self) -> usize {
+        self.outcomes.len() - self.failed()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failures().count()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (&Outcome, &Error)> {
+        self.outcomes
+            .iter()
+            .filter_map(|outcome| Some((outcome, outcome.result.as_ref().err()?)))
+    }
+
+    /// The longest time a single job took, or zero for an empty batch.
+    pub fn slowest(&self) -> Duration {
+        self.outcomes
+            .iter()
+            .map(|outcome| outcome.duration)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Average time a job took, or zero for an empty batch.
+    pub fn average(&self) -> Duration {
+        let total: Duration = self.outcomes.iter().map(|outcome| outcome.duration).sum();
+        match u32::try_from(self.outcomes.len()) {
+            Ok(0) | Err(_) => Duration::ZERO,
+            Ok(count) => total / count,
+        }
+    }
+}
+
+/// The number of workers [`run`] should use by default: one per available core.
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |workers| workers.get())
+}
+
+/// Loads and renders `jobs` on `workers` threads. Workers take the next job from `jobs` as they
+/// free up, so a lazy iterator, e.g. over a stream still being written, is consumed as it goes.
+/// `progress` is called on the calling thread as each job finishes, in completion order.
+pub fn run(
+    renderer: &Renderer,
+    jobs: impl IntoIterator<Item = Job, IntoIter: Send>,
+    workers: usize,
+    mut progress: impl FnMut(&Outcome),
+) -> Summary {
+    let started = Instant::now();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+
+    let mut outcomes = Vec::new();
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            let sender = sender.clone();
+            let queue = &queue;
+            scope.spawn(move || loop {
+                // The lock is released before the job runs, so workers only contend on `next`.
+                let next = queue.lock().unwrap_or_else(|err| err.into_inner()).next();
+                let Some((i, job)) = next else { break };
+                if sender.send((i, execute(renderer, job))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for (i, outcome) in receiver {
+            progress(&outcome);
+            outcomes.push((i, outcome));
+        }
+    });
+
+    outcomes.sort_by_key(|(i, _)| *i);
+    Summary {
+        outcomes: outcomes.into_iter().map(|(_, outcome)| outcome).collect(),
+        elapsed: started.elapsed(),
+    }
+}
+
+fn execute(renderer: &Renderer, job: Job) -> Outcome {
+    let started = Instant::now();
+    let mut warnings = Vec::new();
+    let result = (job.load)().map_err(Error::Input).and_then(|loaded| {
+        warnings = loaded.warnings;
+        write(renderer, &loaded.report, &job.output)
+    });
+    Outcome {
+        name: job.name,
+        output: job.output,
+        warnings,
+        duration: started.elapsed(),
+        result,
+    }
+}
+
+/// Renders `report` into the file at `path`, hashing it on the way. The PDF is written to a
+/// temporary file next to `path` and only moved into place once complete, so a failed render
+/// never leaves a truncated file behind.
+fn write(renderer: &Renderer, report: &Report, path: &Path) -> Result<Rendered, Error> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", Uuid::new_v4()));
+    let temporary = path.with_file_name(name);
+
+    let result = write_to(renderer, report, &temporary).and_then(|rendered| {
+        fs::rename(&temporary, path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(rendered)
+    });
+    if result.is_err() {
+        // It may not even have been created; the original error is the one worth reporting.
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+fn write_to(renderer: &Renderer, report: &Report, path: &Path) -> Result<Rendered, Error> {
+    let file = File::create(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut writer = HashingWriter {
+        inner: file,
+        hasher: Sha256::new(),
+    };
+    let pages = renderer.render(report, &mut writer)?;
+    let sha256 = writer
+        .hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    Ok(Rendered { pages, sha256 })
+}
+
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
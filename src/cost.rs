@@ -0,0 +1,480 @@
+//! Cost basis of a position, replayed from its ledger. The Receita asks for the custo médio
+//! ponderado: purchases and deposits add their cost, sales and withdrawals take out the average
+//! cost of what leaves, and fees paid in the asset shrink the position without lowering its
+//! cost. FIFO is available too, for clients reconciling against their own books.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Datelike;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::model::{Entry, Movement, MovementKind, Statement, Total};
+use crate::money::Money;
+use crate::quantity::Quantity;
+use crate::tax_year::TaxYear;
+
+/// How the cost of what leaves a position is measured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostMethod {
+    /// Custo médio ponderado, the method the Receita accepts.
+    #[default]
+    Average,
+    /// PEPS, or first in, first out: what leaves is taken from the oldest lots.
+    Fifo,
+}
+
+impl CostMethod {
+    pub const ALL: [CostMethod; 2] = [CostMethod::Average, CostMethod::Fifo];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CostMethod::Average => "Custo médio ponderado",
+            CostMethod::Fifo => "PEPS (FIFO)",
+        }
+    }
+
+    fn basis(self) -> Box<dyn CostBasis> {
+        match self {
+            CostMethod::Average => Box::<AverageCost>::default(),
+            CostMethod::Fifo => Box::<Fifo>::default(),
+        }
+    }
+}
+
+impl fmt::Display for CostMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CostMethod::Average => "average",
+            CostMethod::Fifo => "fifo",
+        })
+    }
+}
+
+impl FromStr for CostMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CostMethod::ALL
+            .into_iter()
+            .find(|method| method.to_string() == s)
+            .ok_or_else(|| format!("unknown cost method `{}`, expected `average` or `fifo`", s))
+    }
+}
+
+/// The bookkeeping behind a [`CostMethod`]. Callers check that no more than
+/// [`CostBasis::holding`] is taken out.
+pub trait CostBasis {
+    /// Adds `quantity`, acquired for `cost`.
+    fn acquire(&mut self, quantity: Quantity, cost: Money);
+    /// Takes `quantity` out and returns the cost that leaves with it.
+    fn dispose(&mut self, quantity: Quantity) -> Money;
+    /// Takes `quantity` out while keeping its cost in the position, then adds `cost`.
+    fn fee(&mut self, quantity: Quantity, cost: Money);
+    fn holding(&self) -> Holding;
+}
+
+/// Quantity held and its acquisition value in BRL.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Holding {
+    pub quantity: Quantity,
+    pub cost: Money,
+}
+
+impl Holding {
+    /// Weighted average acquisition cost of one unit, or `None` while nothing is held.
+    pub fn average(&self) -> Option<Money> {
+        (!self.quantity.is_zero()).then(|| self.cost / self.quantity.amount())
+    }
+
+    pub fn total(&self) -> Total {
+        Total {
+            quantity: self.quantity,
+            cost: self.cost,
+            price: self.average(),
+        }
+    }
+}
+
+impl From<Total> for Holding {
+    fn from(total: Total) -> Self {
+        Holding {
+            quantity: total.quantity,
+            cost: total.cost,
+        }
+    }
+}
+
+/// An entry of a ledger that can't be replayed.
+#[derive(Debug)]
+pub struct LedgerError {
+    /// Index of the entry in the ledger.
+    pub entry: usize,
+    pub reason: String,
+}
+
+/// The statements of the ano-calendário and of the previous year, with their totals set to the
+/// position at each 31/12. The previous one is `None` if the asset wasn't held or moved then.
+pub struct Statements {
+    pub current: Statement,
+    pub previous: Option<Statement>,
+}
+
+/// Replays `ledger` up to the end of `tax_year` with `method`. Later entries are left out, so one
+/// ledger can be kept for every year and replayed for any of them.
+pub fn statements(
+    ledger: &[Entry],
+    tax_year: TaxYear,
+    method: CostMethod,
+) -> Result<Statements, LedgerError> {
+    let year = i32::from(tax_year.year());
+    let mut basis = method.basis();
+    let mut previous_end = None;
+    let mut previous = Vec::new();
+    let mut current = Vec::new();
+
+    for (i, entry) in ledger.iter().enumerate() {
+        let error = |reason: String| LedgerError { entry: i, reason };
+        if i > 0 && entry.date < ledger[i - 1].date {
+            return Err(error("is dated before the entry preceding it".to_string()));
+        }
+        let entry_year = entry.date.year();
+        if entry_year > year {
+            continue;
+        }
+        if entry_year == year && previous_end.is_none() {
+            previous_end = Some(basis.holding());
+        }
+
+        let movement = replay(basis.as_mut(), entry).map_err(error)?;
+        if entry_year == year {
+            current.push(movement);
+        } else if entry_year == year - 1 {
+            previous.push(movement);
+        }
+    }
+    let holding = basis.holding();
+    let previous_end = previous_end.unwrap_or(holding);
+
+    let previous = (!previous.is_empty() || !previous_end.quantity.is_zero()).then(|| Statement {
+        movements: previous,
+        total: Some(previous_end.total()),
+    });
+    Ok(Statements {
+        current: Statement {
+            movements: current,
+            total: Some(holding.total()),
+        },
+        previous,
+    })
+}
+
+/// The statements of one ledger under every [`CostMethod`], to compare them side by side.
+pub fn compare(
+    ledger: &[Entry],
+    tax_year: TaxYear,
+) -> Result<Vec<(CostMethod, Statements)>, LedgerError> {
+    CostMethod::ALL
+        .into_iter()
+        .map(|method| Ok((method, statements(ledger, tax_year, method)?)))
+        .collect()
+}
+
+/// Applies `entry` to `basis` and returns its row in the statement, with the cost it added to or
+/// took out of the position.
+fn replay(basis: &mut dyn CostBasis, entry: &Entry) -> Result<Movement, String> {
+    let quantity = entry.quantity;
+    let unit_price = |cost: Money| (!quantity.is_zero()).then(|| cost / quantity.amount());
+    let movement = |cost: Option<Money>, price: Option<Money>| Movement {
+        kind: entry.kind,
+        quantity,
+        cost,
+        price,
+    };
+
+    let held = basis.holding().quantity;
+    if entry.kind.is_outflow() && quantity.amount() > held.amount() {
+        return Err(format!(
+            "takes out {} but only {} is held",
+            quantity.amount().normalize(),
+            held.amount().normalize()
+        ));
+    }
+
+    match entry.kind {
+        MovementKind::Bought | MovementKind::Deposited => {
+            if matches!(entry.kind, MovementKind::Bought) && entry.cost.is_none() {
+                return Err("purchases need a `cost`".to_string());
+            }
+            let cost = entry.cost;
+            basis.acquire(quantity, cost.unwrap_or_default());
+            Ok(movement(cost, cost.and_then(unit_price)))
+        }
+        MovementKind::Fee => {
+            basis.fee(quantity, entry.cost.unwrap_or_default());
+            Ok(movement(entry.cost, None))
+        }
+        MovementKind::Sold | MovementKind::Withdrawn => {
+            if entry.cost.is_some() {
+                return Err("only purchases, deposits and fees carry a `cost`".to_string());
+            }
+            let taken = basis.dispose(quantity);
+            Ok(movement(Some(taken), unit_price(taken)))
+        }
+    }
+}
+
+/// Weighted average cost, also used to total hand-typed statements.
+#[derive(Default)]
+pub(crate) struct AverageCost(pub(crate) Holding);
+
+impl CostBasis for AverageCost {
+    fn acquire(&mut self, quantity: Quantity, cost: Money) {
+        self.0.quantity = self.0.quantity + quantity;
+        self.0.cost = self.0.cost + cost;
+    }
+
+    fn dispose(&mut self, quantity: Quantity) -> Money {
+        let holding = &mut self.0;
+        // Taking out everything (or, from an invalid statement, more) takes the whole cost.
+        let taken = if quantity.amount() >= holding.quantity.amount() {
+            holding.cost
+        } else {
+            holding.cost * (quantity.amount() / holding.quantity.amount())
+        };
+        holding.quantity = holding.quantity + -quantity;
+        holding.cost = Money::from(holding.cost.amount() - taken.amount());
+        taken
+    }
+
+    fn fee(&mut self, quantity: Quantity, cost: Money) {
+        self.0.quantity = self.0.quantity + -quantity;
+        self.0.cost = self.0.cost + cost;
+    }
+
+    fn holding(&self) -> Holding {
+        self.0
+    }
+}
+
+/// Lots in the order they were acquired.
+#[derive(Default)]
+struct Fifo(VecDeque<Holding>);
+
+impl CostBasis for Fifo {
+    fn acquire(&mut self, quantity: Quantity, cost: Money) {
+        self.0.push_back(Holding { quantity, cost });
+    }
+
+    fn dispose(&mut self, quantity: Quantity) -> Money {
+        let mut remaining = quantity.amount();
+        let mut taken = Money::ZERO;
+        while remaining > Decimal::ZERO {
+            let Some(lot) = self.0.front_mut() else { break };
+            if lot.quantity.amount() <= remaining {
+                remaining -= lot.quantity.amount();
+                taken = taken + lot.cost;
+                self.0.pop_front();
+            } else {
+                let part = lot.cost * (remaining / lot.quantity.amount());
+                lot.quantity = lot.quantity + -Quantity::from(remaining);
+                lot.cost = Money::from(lot.cost.amount() - part.amount());
+                taken = taken + part;
+                remaining = Decimal::ZERO;
+            }
+        }
+        taken
+    }
+
+    /// The fee comes out of the oldest lots, and their cost moves to the oldest lot left.
+    fn fee(&mut self, quantity: Quantity, cost: Money) {
+        let kept = self.dispose(quantity) + cost;
+        match self.0.front_mut() {
+            Some(lot) => lot.cost = lot.cost + kept,
+            None => self.0.push_back(Holding {
+                quantity: Quantity::default(),
+                cost: kept,
+            }),
+        }
+    }
+
+    fn holding(&self) -> Holding {
+        Holding {
+            quantity: self.0.iter().map(|lot| lot.quantity).sum(),
+            cost: self.0.iter().map(|lot| lot.cost).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(json: &str) -> Vec<Entry> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn replay(json: &str, year: u16, method: CostMethod) -> Statements {
+        statements(&ledger(json), TaxYear::new(year), method).unwrap()
+    }
+
+    fn quantity(total: &Total) -> String {
+        total.quantity.amount().normalize().to_string()
+    }
+
+    /// Two purchases in 2020, then a sale, a deposit, a fee in the asset and a withdrawal.
+    const TRADES: &str = r#"[
+        { "date": "2020-03-10", "kind": "bought", "quantity": 0.4, "cost": 16000 },
+        { "date": "2020-11-02", "kind": "bought", "quantity": 0.2, "cost": 18000 },
+        { "date": "2021-02-15", "kind": "sold", "quantity": 0.3 },
+        { "date": "2021-06-01", "kind": "deposited", "quantity": 0.1, "cost": 15000 },
+        { "date": "2021-06-01", "kind": "fee", "quantity": 0.0005 },
+        { "date": "2021-09-20", "kind": "withdrawn", "quantity": 0.05 }
+    ]"#;
+
+    #[test]
+    fn average_cost_through_sales_fees_and_withdrawals() {
+        let statements = replay(TRADES, 2021, CostMethod::Average);
+        let movements = &statements.current.movements;
+        assert_eq!(movements[0].cost.unwrap().to_string(), "R$ 17.000,00");
+        assert_eq!(movements[0].price.unwrap().to_string(), "R$ 56.666,67");
+        assert_eq!(movements[2].cost, None);
+        assert_eq!(movements[3].cost.unwrap().to_string(), "R$ 4.005,01");
+
+        let total = statements.current.total(None);
+        assert_eq!(quantity(&total), "0.3495");
+        assert_eq!(total.cost.to_string(), "R$ 27.994,99");
+        assert_eq!(total.price.unwrap().to_string(), "R$ 80.100,13");
+    }
+
+    #[test]
+    fn replays_a_ledger_spanning_years_for_each_tax_year() {
+        let statements = replay(TRADES, 2020, CostMethod::Average);
+        assert!(statements.previous.is_none());
+        assert_eq!(statements.current.movements.len(), 2);
+        let total = statements.current.total(None);
+        assert_eq!(quantity(&total), "0.6");
+        assert_eq!(total.cost.to_string(), "R$ 34.000,00");
+
+        let statements = replay(TRADES, 2021, CostMethod::Average);
+        let previous = statements.previous.unwrap();
+        assert_eq!(previous.movements.len(), 2);
+        assert_eq!(previous.total(None).cost.to_string(), "R$ 34.000,00");
+        assert_eq!(statements.current.movements.len(), 4);
+
+        // Held through 2022 without moving: no movements, the balance of 2021.
+        let statements = replay(TRADES, 2022, CostMethod::Average);
+        assert!(statements.current.movements.is_empty());
+        let previous = statements.previous.unwrap();
+        assert_eq!(previous.movements.len(), 4);
+        assert_eq!(
+            previous.total(None).cost,
+            statements.current.total(None).cost
+        );
+    }
+
+    #[test]
+    fn rejects_entries_that_cannot_be_replayed() {
+        let error = |json: &str| {
+            statements(&ledger(json), TaxYear::new(2021), CostMethod::Average)
+                .err()
+                .unwrap()
+                .entry
+        };
+        let oversold = r#"[
+            { "date": "2021-01-01", "kind": "bought", "quantity": 1, "cost": 100 },
+            { "date": "2021-01-02", "kind": "sold", "quantity": 2 }
+        ]"#;
+        assert_eq!(error(oversold), 1);
+        let unordered = r#"[
+            { "date": "2021-02-01", "kind": "bought", "quantity": 1, "cost": 100 },
+            { "date": "2021-01-01", "kind": "bought", "quantity": 1, "cost": 100 }
+        ]"#;
+        assert_eq!(error(unordered), 1);
+        let unpriced = r#"[{ "date": "2021-01-01", "kind": "bought", "quantity": 1 }]"#;
+        assert_eq!(error(unpriced), 0);
+    }
+
+    #[test]
+    fn fifo_takes_sales_from_the_oldest_lots() {
+        let statements = replay(TRADES, 2021, CostMethod::Fifo);
+        let movements = &statements.current.movements;
+        assert_eq!(movements[0].cost.unwrap().to_string(), "R$ 12.000,00");
+        // The fee's 0.0005 leaves the 2020 lot, whose cost stays: 0.05 of 0.0995 for R$ 4.000.
+        assert_eq!(movements[3].cost.unwrap().to_string(), "R$ 2.010,05");
+
+        let total = statements.current.total(None);
+        assert_eq!(quantity(&total), "0.3495");
+        assert_eq!(total.cost.to_string(), "R$ 34.989,95");
+
+        let previous = statements.previous.unwrap().total(None);
+        assert_eq!(previous.cost.to_string(), "R$ 34.000,00");
+    }
+
+    #[test]
+    fn fee_using_up_every_lot_keeps_their_cost() {
+        let json = r#"[
+            { "date": "2021-01-01", "kind": "bought", "quantity": 0.1, "cost": 100 },
+            { "date": "2021-01-02", "kind": "bought", "quantity": 0.1, "cost": 200 },
+            { "date": "2021-01-03", "kind": "fee", "quantity": 0.2 },
+            { "date": "2021-01-04", "kind": "bought", "quantity": 0.1, "cost": 50 },
+            { "date": "2021-01-05", "kind": "sold", "quantity": 0.1 }
+        ]"#;
+        for method in CostMethod::ALL {
+            let mut basis = method.basis();
+            let entries = ledger(json);
+            for entry in &entries[..3] {
+                super::replay(basis.as_mut(), entry).unwrap();
+            }
+            let held = basis.holding();
+            assert!(held.quantity.is_zero(), "{}", method);
+            assert_eq!(held.cost.to_string(), "R$ 300,00", "{}", method);
+            assert_eq!(held.average(), None, "{}", method);
+
+            let statements = replay(json, 2021, method);
+            let sale = &statements.current.movements[4];
+            assert_eq!(sale.cost.unwrap().to_string(), "R$ 350,00", "{}", method);
+            let total = statements.current.total(None);
+            assert!(total.quantity.is_zero(), "{}", method);
+            assert_eq!(total.cost, Money::ZERO, "{}", method);
+        }
+    }
+
+    #[test]
+    fn fifo_replays_a_ledger_spanning_years_for_each_tax_year() {
+        let json = r#"[
+            { "date": "2019-05-01", "kind": "bought", "quantity": 1, "cost": 10 },
+            { "date": "2020-05-01", "kind": "bought", "quantity": 1, "cost": 30 },
+            { "date": "2021-05-01", "kind": "sold", "quantity": 1.5 }
+        ]"#;
+        let statements = replay(json, 2020, CostMethod::Fifo);
+        assert_eq!(
+            statements.previous.unwrap().total(None).cost.to_string(),
+            "R$ 10,00"
+        );
+        assert_eq!(statements.current.total(None).cost.to_string(), "R$ 40,00");
+
+        let statements = replay(json, 2021, CostMethod::Fifo);
+        assert_eq!(
+            statements.current.movements[0].cost.unwrap().to_string(),
+            "R$ 25,00"
+        );
+        let total = statements.current.total(None);
+        assert_eq!(quantity(&total), "0.5");
+        assert_eq!(total.cost.to_string(), "R$ 15,00");
+
+        let average = replay(json, 2021, CostMethod::Average).current.total(None);
+        assert_eq!(average.cost.to_string(), "R$ 10,00");
+    }
+
+    #[test]
+    fn parses_the_method_names() {
+        for method in CostMethod::ALL {
+            assert_eq!(method.to_string().parse::<CostMethod>(), Ok(method));
+        }
+        assert!("lifo".parse::<CostMethod>().is_err());
+    }
+}
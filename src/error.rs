@@ -0,0 +1,76 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::input::InputError;
+
+/// Everything that can go wrong between a report input and its PDF.
+#[derive(Debug)]
+pub enum Error {
+    /// The input document could not be read, decoded or validated.
+    Input(InputError),
+    /// The Roboto font family could not be loaded from `dir`, or from the embedded copy if
+    /// `dir` is `None`.
+    FontMissing {
+        dir: Option<PathBuf>,
+        source: genpdf::error::Error,
+    },
+    /// The logo at `path`, or the embedded one if `path` is `None`, could not be read or
+    /// decoded.
+    ImageUnreadable {
+        path: Option<PathBuf>,
+        source: genpdf::error::Error,
+    },
+    /// A table row did not match the number of columns of its table.
+    InvalidTableRow(genpdf::error::Error),
+    /// genpdf failed to lay out the document or to write it.
+    Render(genpdf::error::Error),
+    /// The output file at `path` could not be created or moved into place.
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Input(err) => err.fmt(f),
+            Error::FontMissing {
+                dir: Some(dir),
+                source,
+            } => write!(f, "could not load fonts from {}: {}", dir.display(), source),
+            Error::FontMissing { dir: None, source } => {
+                write!(f, "could not load the embedded fonts: {}", source)
+            }
+            Error::ImageUnreadable {
+                path: Some(path),
+                source,
+            } => write!(f, "could not load image {}: {}", path.display(), source),
+            Error::ImageUnreadable { path: None, source } => {
+                write!(f, "could not load the embedded logo: {}", source)
+            }
+            Error::InvalidTableRow(source) => write!(f, "invalid table row: {}", source),
+            Error::Render(source) => write!(f, "could not render the document: {}", source),
+            Error::Io { path, source } => {
+                write!(f, "could not write {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Input(err) => Some(err),
+            Error::FontMissing { source, .. }
+            | Error::ImageUnreadable { source, .. }
+            | Error::InvalidTableRow(source)
+            | Error::Render(source) => Some(source),
+            Error::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<InputError> for Error {
+    fn from(err: InputError) -> Self {
+        Error::Input(err)
+    }
+}
@@ -0,0 +1,467 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::asset::AssetDescriptor;
+use crate::cost;
+use crate::model::{MovementKind, Report, Statement, Total};
+use crate::money::Money;
+use crate::quantity::Quantity;
+use crate::tax_year::Period;
+
+/// Failure to read or decode a report input document.
+#[derive(Debug)]
+pub enum InputError {
+    Io { path: PathBuf, source: io::Error },
+    Invalid { field: String, reason: String },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+            InputError::Invalid { field, reason } => write!(f, "{}: {}", field, reason),
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Io { source, .. } => Some(source),
+            InputError::Invalid { .. } => None,
+        }
+    }
+}
+
+/// What to do with a CPF or CNPJ whose check digits don't match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TaxIdPolicy {
+    /// Fail loading the report.
+    #[default]
+    Reject,
+    /// Load the report anyway and list the problem in [`Loaded::warnings`].
+    Warn,
+}
+
+/// A decoded report together with the problems the [`TaxIdPolicy`] let through.
+pub struct Loaded {
+    pub report: Report,
+    pub warnings: Vec<InputError>,
+}
+
+/// Reads the JSON report input at `path`.
+pub fn load(path: &Path, policy: TaxIdPolicy) -> Result<Loaded, InputError> {
+    let json = fs::read_to_string(path).map_err(|source| InputError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    from_json(&json, policy)
+}
+
+/// Decodes a JSON report input. Errors point at the offending field, e.g.
+/// `positions[1].current.movements[2].kind`.
+pub fn from_json(json: &str, policy: TaxIdPolicy) -> Result<Loaded, InputError> {
+    let de = &mut serde_json::Deserializer::from_str(json);
+    let mut report: Report = serde_path_to_error::deserialize(de).map_err(|err| {
+        let field = match err.path().to_string() {
+            path if path == "." => "<root>".to_string(),
+            path => path,
+        };
+        InputError::Invalid {
+            field,
+            reason: err.into_inner().to_string(),
+        }
+    })?;
+    validate(&report)?;
+    apply_ledgers(&mut report)?;
+
+    let mut warnings = check_tax_ids(&report);
+    if policy == TaxIdPolicy::Reject && !warnings.is_empty() {
+        return Err(warnings.remove(0));
+    }
+    Ok(Loaded { report, warnings })
+}
+
+fn check_tax_ids(report: &Report) -> Vec<InputError> {
+    let cnpjs = [
+        ("payer.cnpj", &report.payer),
+        ("custodian.cnpj", &report.custodian),
+    ];
+    let invalid_cnpjs = cnpjs
+        .into_iter()
+        .filter_map(|(field, entity)| Some((field, entity.as_ref()?.cnpj)))
+        .filter(|(_, cnpj)| !cnpj.is_valid())
+        .map(|(field, cnpj)| invalid(field, format!("CNPJ {} has invalid check digits", cnpj)));
+
+    let cpf = &report.beneficiary.cpf;
+    let invalid_cpf = (!cpf.is_valid()).then(|| {
+        invalid(
+            "beneficiary.cpf",
+            format!("CPF {} has invalid check digits", cpf),
+        )
+    });
+
+    invalid_cpf.into_iter().chain(invalid_cnpjs).collect()
+}
+
+fn validate(report: &Report) -> Result<(), InputError> {
+    if report.tax_year.year() < 2000 {
+        return Err(invalid("tax_year", "must be 2000 or later"));
+    }
+
+    for (i, position) in report.positions.iter().enumerate() {
+        let field = format!("positions[{}]", i);
+        let asset = report.asset(&position.asset).ok_or_else(|| {
+            invalid(
+                &format!("{}.asset", field),
+                format!(
+                    "unknown asset `{}`, add a descriptor for it under `assets`",
+                    position.asset
+                ),
+            )
+        })?;
+
+        if let Some(previous) = &position.previous {
+            validate_statement(&format!("{}.previous", field), previous, None, &asset)?;
+        }
+        validate_statement(
+            &format!("{}.current", field),
+            &position.current,
+            position.opening(Period::Current).as_ref(),
+            &asset,
+        )?;
+
+        if let Some(ledger) = &position.ledger {
+            let typed_in = !position.current.movements.is_empty()
+                || position.current.total.is_some()
+                || position.previous.is_some();
+            if typed_in {
+                return Err(invalid(
+                    &format!("{}.ledger", field),
+                    "statements are derived from the ledger, leave out `current` and `previous`",
+                ));
+            }
+            for (j, entry) in ledger.iter().enumerate() {
+                let field = format!("{}.ledger[{}]", field, j);
+                if entry.quantity.is_sign_negative() {
+                    return Err(invalid(
+                        &format!("{}.quantity", field),
+                        "must not be negative, the entry kind sets the sign",
+                    ));
+                }
+                if entry
+                    .cost
+                    .is_some_and(|cost| cost.amount().is_sign_negative())
+                {
+                    return Err(invalid(&format!("{}.cost", field), "must not be negative"));
+                }
+                check_quantity(&field, entry.quantity, &asset)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Derives the statements of every position with a ledger for the report's tax year and cost
+/// method. Loading already does this; call it again after changing [`Report::tax_year`] or
+/// [`Report::cost_method`].
+pub fn apply_ledgers(report: &mut Report) -> Result<(), InputError> {
+    let (tax_year, method) = (report.tax_year, report.cost_method);
+    for (i, position) in report.positions.iter_mut().enumerate() {
+        let Some(ledger) = &position.ledger else {
+            continue;
+        };
+        let statements = cost::statements(ledger, tax_year, method).map_err(|err| {
+            invalid(
+                &format!("positions[{}].ledger[{}]", i, err.entry),
+                err.reason,
+            )
+        })?;
+        position.current = statements.current;
+        position.previous = statements.previous;
+    }
+    Ok(())
+}
+
+fn validate_statement(
+    field: &str,
+    statement: &Statement,
+    opening: Option<&Total>,
+    asset: &AssetDescriptor,
+) -> Result<(), InputError> {
+    for (i, movement) in statement.movements.iter().enumerate() {
+        let field = format!("{}.movements[{}]", field, i);
+        if movement.quantity.is_sign_negative() {
+            return Err(invalid(
+                &format!("{}.quantity", field),
+                "must not be negative, the movement kind sets the sign",
+            ));
+        }
+        check_quantity(&field, movement.quantity, asset)?;
+
+        let is_purchase = matches!(movement.kind, MovementKind::Bought);
+        let priced = movement.cost.is_some() && movement.price.is_some();
+        if is_purchase && !priced {
+            return Err(invalid(&field, "purchases need both `cost` and `price`"));
+        }
+        if !is_purchase && (movement.cost.is_some() || movement.price.is_some()) {
+            return Err(invalid(&field, "only purchases carry `cost` and `price`"));
+        }
+        if let (Some(cost), Some(price)) = (movement.cost, movement.price) {
+            if cost.amount().is_sign_negative() {
+                return Err(invalid(&format!("{}.cost", field), "must not be negative"));
+            }
+            let quantity = movement.quantity;
+            if !quantity.is_zero() && !printed_alike(price, cost / quantity.amount()) {
+                return Err(invalid(
+                    &format!("{}.price", field),
+                    format!(
+                        "does not match the cost, {} for {} is {} each",
+                        cost,
+                        quantity.format(asset),
+                        cost / quantity.amount()
+                    ),
+                ));
+            }
+        }
+    }
+
+    let computed = statement.computed_total(opening);
+    if computed.quantity.is_sign_negative() {
+        return Err(invalid(
+            &format!("{}.movements", field),
+            format!(
+                "take out more than is held, leaving {}",
+                computed.quantity.amount().normalize()
+            ),
+        ));
+    }
+    if let Some(total) = &statement.total {
+        let field = format!("{}.total", field);
+        check_quantity(&field, total.quantity, asset)?;
+        check_total(&field, total, &computed)?;
+    }
+    Ok(())
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> InputError {
+    InputError::Invalid {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+fn check_quantity(
+    field: &str,
+    quantity: Quantity,
+    asset: &AssetDescriptor,
+) -> Result<(), InputError> {
+    let field = format!("{}.quantity", field);
+    if quantity.decimals() > asset.decimals as u32 {
+        return Err(invalid(
+            &field,
+            format!(
+                "{} has more than the {} decimal places {} supports",
+                quantity.amount(),
+                asset.decimals,
+                asset.ticker
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether two amounts print the same, at the 2 decimal places of the tables.
+fn printed_alike(a: Money, b: Money) -> bool {
+    a.round(2) == b.round(2)
+}
+
+/// Rejects a supplied total that disagrees with the one derived from the opening balance and the
+/// movements. Cost and price are compared at the 2 decimal places they are printed with, so a
+/// total that passes never reads differently from the rows above it.
+fn check_total(field: &str, supplied: &Total, computed: &Total) -> Result<(), InputError> {
+    let price_matches = match (supplied.price, computed.price) {
+        (Some(supplied), Some(computed)) => printed_alike(supplied, computed),
+        (None, None) => true,
+        _ => false,
+    };
+
+    if supplied.quantity == computed.quantity
+        && printed_alike(supplied.cost, computed.cost)
+        && price_matches
+    {
+        return Ok(());
+    }
+
+    let price = computed
+        .price
+        .map_or_else(|| "none".to_string(), |price| price.to_string());
+    Err(invalid(
+        field,
+        format!(
+            "does not reconcile with the opening balance and movements, which add up to quantity {}, cost {} and price {}",
+            computed.quantity.amount().normalize(),
+            computed.cost,
+            price
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A report holding only `positions`, given as JSON.
+    fn report(positions: &str) -> Result<Report, InputError> {
+        let json = format!(
+            r#"{{
+                "tax_year": 2021,
+                "beneficiary": {{
+                    "name": "Felipe Rosa",
+                    "cpf": "529.982.247-25",
+                    "agency": "0001",
+                    "account": "0020332"
+                }},
+                "fixed_income": {{ "opening_balance": 0, "closing_balance": 0, "net_income": 0 }},
+                "positions": {}
+            }}"#,
+            positions
+        );
+        from_json(&json, TaxIdPolicy::Reject).map(|loaded| loaded.report)
+    }
+
+    fn rejected_field(result: Result<Report, InputError>) -> String {
+        match result {
+            Err(InputError::Invalid { field, .. }) => field,
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("the report was accepted"),
+        }
+    }
+
+    #[test]
+    fn rejects_an_integer_total_that_prints_differently() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "current": {
+                    "movements": [{ "kind": "bought", "quantity": 1, "cost": 0.4, "price": 0.4 }],
+                    "total": { "quantity": 1, "cost": 0, "price": 0 }
+                }
+            }]"#,
+        );
+        assert_eq!(rejected_field(result), "positions[0].current.total");
+    }
+
+    #[test]
+    fn accepts_a_total_that_prints_the_same() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "current": {
+                    "movements": [{ "kind": "bought", "quantity": 3, "cost": 100, "price": 33.33 }],
+                    "total": { "quantity": 3, "cost": 100.00, "price": 33.33 }
+                }
+            }]"#,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero_like_the_printed_amounts() {
+        let positions = |total_cost: &str| {
+            format!(
+                r#"[{{
+                    "asset": "BTC",
+                    "current": {{
+                        "movements": [{{ "kind": "bought", "quantity": 1, "cost": 10.125, "price": 10.125 }}],
+                        "total": {{ "quantity": 1, "cost": {}, "price": 10.125 }}
+                    }}
+                }}]"#,
+                total_cost
+            )
+        };
+        assert!(report(&positions("10.13")).is_ok());
+        assert!(report(&positions("10.12")).is_err());
+    }
+
+    #[test]
+    fn opens_the_current_year_with_the_previous_balance() {
+        let report = report(
+            r#"[{
+                "asset": "BTC",
+                "previous": {
+                    "movements": [{ "kind": "bought", "quantity": 1, "cost": 100, "price": 100 }]
+                },
+                "current": {
+                    "movements": [{ "kind": "sold", "quantity": 0.5 }],
+                    "total": { "quantity": 0.5, "cost": 50, "price": 100 }
+                }
+            }]"#,
+        )
+        .unwrap();
+        let total = report.positions[0].total(Period::Current).unwrap();
+        assert_eq!(total.quantity.amount().to_string(), "0.5");
+        assert_eq!(total.cost.to_string(), "R$ 50,00");
+    }
+
+    #[test]
+    fn rejects_selling_more_than_is_held() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "current": { "movements": [{ "kind": "sold", "quantity": 0.5 }] }
+            }]"#,
+        );
+        assert_eq!(rejected_field(result), "positions[0].current.movements");
+    }
+
+    #[test]
+    fn rejects_a_negative_ledger_cost() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "ledger": [
+                    { "date": "2021-01-04", "kind": "bought", "quantity": 1, "cost": 1000 },
+                    { "date": "2021-02-01", "kind": "bought", "quantity": 1, "cost": -500 }
+                ]
+            }]"#,
+        );
+        assert_eq!(rejected_field(result), "positions[0].ledger[1].cost");
+    }
+
+    #[test]
+    fn rejects_a_purchase_price_that_disagrees_with_its_cost() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "current": {
+                    "movements": [{ "kind": "bought", "quantity": 1, "cost": 100, "price": 7 }]
+                }
+            }]"#,
+        );
+        assert_eq!(
+            rejected_field(result),
+            "positions[0].current.movements[0].price"
+        );
+    }
+
+    #[test]
+    fn rejects_a_negative_purchase_cost() {
+        let result = report(
+            r#"[{
+                "asset": "BTC",
+                "current": {
+                    "movements": [{ "kind": "bought", "quantity": 1, "cost": -100, "price": -100 }]
+                }
+            }]"#,
+        );
+        assert_eq!(
+            rejected_field(result),
+            "positions[0].current.movements[0].cost"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Building blocks the report is assembled from. They are public so that services can compose
+//! their own documents with the same look as the Informe.
+
+use genpdf::elements::{LinearLayout, TableLayout};
+use genpdf::style::{Color, Style};
+use genpdf::{elements, Alignment, Element, Margins};
+
+use crate::asset::AssetDescriptor;
+use crate::error::Error;
+use crate::model::{Statement, Total};
+use crate::money::Money;
+use crate::quantity::Quantity;
+use crate::receita::Ficha;
+use crate::tax_year::{Period, TaxYear};
+use crate::theme::{Spacing, Theme};
+
+/// One cell of a [`row_table`].
+pub struct RowData {
+    pub text: String,
+    pub style: Style,
+    pub alignment: Alignment,
+}
+
+impl RowData {
+    pub fn new(text: &str, style: Style, alignment: Alignment) -> Self {
+        Self {
+            text: text.to_string(),
+            style,
+            alignment,
+        }
+    }
+}
+
+/// Appends an empty row to `table`, which closes a [`build_table`] with a bottom rule.
+pub fn add_empty_row(table: &mut TableLayout) -> Result<(), Error> {
+    (*table)
+        .row()
+        .element(elements::Break::new(0))
+        .push()
+        .map_err(Error::InvalidTableRow)
+}
+
+/// Stacks `data` in a single-column table with a horizontal rule above each element.
+pub fn build_table<E: Element + 'static>(
+    data: Vec<E>,
+    padding: Margins,
+) -> Result<TableLayout, Error> {
+    let mut table = elements::TableLayout::new(vec![1]);
+    table.set_cell_decorator(elements::FrameCellDecorator::new(true, false, false));
+
+    for d in data {
+        table
+            .row()
+            .element(d.padded(padding))
+            .push()
+            .map_err(Error::InvalidTableRow)?;
+    }
+
+    Ok(table)
+}
+
+/// Lays out `data` as one row of equally wide cells.
+pub fn row_table(theme: &Theme, data: Vec<RowData>) -> Result<TableLayout, Error> {
+    let mut table = elements::TableLayout::new(data.iter().map(|_| 1).collect());
+    table.set_cell_decorator(elements::FrameCellDecorator::new(false, false, false));
+    let padding = Spacing::below(theme.spacing.cell_padding);
+
+    let mut row = table.row();
+    for d in data {
+        row = row.element(
+            elements::Paragraph::new(d.text)
+                .aligned(d.alignment)
+                .styled(d.style)
+                .padded(padding),
+        )
+    }
+    row.push().map_err(Error::InvalidTableRow)?;
+
+    Ok(table)
+}
+
+/// Titled block: a title in the brand color, a bold subtitle and `element` below them, as used
+/// for the paying source and beneficiary identification.
+pub fn linear_layout<E: Element + 'static>(
+    theme: &Theme,
+    title: &str,
+    subtitle: &str,
+    element: E,
+) -> LinearLayout {
+    let mut layout = elements::LinearLayout::vertical();
+    let padding = Spacing::below(theme.spacing.block_padding);
+    let size = theme.typography.subheading;
+
+    layout.push(
+        elements::Paragraph::new(title)
+            .aligned(Alignment::Left)
+            .styled(Style::new().bold().with_font_size(size))
+            .styled(Color::from(theme.palette.brand))
+            .padded(padding),
+    );
+
+    layout.push(
+        elements::Paragraph::new(subtitle)
+            .aligned(Alignment::Left)
+            .styled(Style::new().bold().with_font_size(size))
+            .padded(padding)
+            .styled(Color::from(theme.palette.text)),
+    );
+
+    layout.push(element);
+
+    layout
+}
+
+/// Heading of an asset section: an optional page title, the asset heading in the asset's
+/// accent color and `element` below them.
+pub fn asset_linear_layout<E: Element + 'static>(
+    theme: &Theme,
+    title: Option<&str>,
+    subtitle: &str,
+    accent: Color,
+    element: E,
+) -> LinearLayout {
+    let mut layout = elements::LinearLayout::vertical();
+    let padding = Spacing::below(theme.spacing.block_padding);
+
+    if let Some(title) = title {
+        layout.push(
+            elements::Paragraph::new(title)
+                .aligned(Alignment::Left)
+                .styled(Style::new().bold().with_font_size(theme.typography.heading))
+                .styled(Color::from(theme.palette.text))
+                .padded(padding),
+        );
+    }
+
+    layout.push(
+        elements::Paragraph::new(subtitle)
+            .aligned(Alignment::Left)
+            .styled(
+                Style::new()
+                    .bold()
+                    .with_font_size(theme.typography.subheading),
+            )
+            .padded(padding)
+            .styled(accent),
+    );
+
+    layout.push(element);
+
+    layout
+}
+
+/// The Fonte/Saldo/Custo/Preço table of a statement, opened by the balance carried over from
+/// the previous year, if any, and closed by its Total row.
+pub fn asset_table(
+    theme: &Theme,
+    asset: &AssetDescriptor,
+    statement: &Statement,
+    opening: Option<&Total>,
+) -> Result<TableLayout, Error> {
+    let size = theme.typography.table;
+    let table_header_style = Style::new()
+        .with_color(theme.palette.rule.into())
+        .with_font_size(size);
+    let table_value = Style::new()
+        .with_color(theme.palette.text.into())
+        .with_font_size(size);
+    let total_style = Style::new().with_color(asset.accent()).with_font_size(size);
+
+    let mut rows = vec![row_table(
+        theme,
+        vec![
+            RowData::new("Fonte", table_header_style, Alignment::Left),
+            RowData::new("Saldo", table_header_style, Alignment::Left),
+            RowData::new("Custo", table_header_style, Alignment::Left),
+            RowData::new("Preço", table_header_style, Alignment::Left),
+        ],
+    )?];
+
+    let quantity = |quantity: Quantity| quantity.format(asset);
+    let brl = |value: Option<Money>| value.map_or_else(|| "N/A".to_string(), |v| v.to_string());
+
+    if let Some(opening) = opening {
+        rows.push(row_table(
+            theme,
+            vec![
+                RowData::new("Saldo anterior", table_value, Alignment::Left),
+                RowData::new(&quantity(opening.quantity), table_value, Alignment::Left),
+                RowData::new(&brl(Some(opening.cost)), table_value, Alignment::Left),
+                RowData::new(&brl(opening.price), table_value, Alignment::Left),
+            ],
+        )?);
+    }
+    for movement in &statement.movements {
+        rows.push(row_table(
+            theme,
+            vec![
+                RowData::new(movement.kind.label(), table_value, Alignment::Left),
+                RowData::new(
+                    &quantity(movement.signed_quantity()),
+                    table_value,
+                    Alignment::Left,
+                ),
+                RowData::new(&brl(movement.signed_cost()), table_value, Alignment::Left),
+                RowData::new(&brl(movement.price), table_value, Alignment::Left),
+            ],
+        )?);
+    }
+
+    let total = statement.total(opening);
+    rows.push(row_table(
+        theme,
+        vec![
+            RowData::new("Total", total_style, Alignment::Left),
+            RowData::new(&quantity(total.quantity), total_style, Alignment::Left),
+            RowData::new(&brl(Some(total.cost)), total_style, Alignment::Left),
+            RowData::new(&brl(total.price), total_style, Alignment::Left),
+        ],
+    )?);
+
+    build_table(rows, Spacing::below(theme.spacing.row_padding))
+}
+
+/// Framed box with a ready-to-paste Discriminação, highlighted in `accent`.
+pub fn discriminacao_box(theme: &Theme, accent: Color, text: &str) -> impl Element {
+    let mut layout = elements::LinearLayout::vertical();
+    layout.push(
+        elements::Paragraph::new("Discriminação")
+            .styled(
+                Style::new()
+                    .bold()
+                    .with_font_size(theme.typography.table)
+                    .with_color(accent),
+            )
+            .padded(Spacing::below(theme.spacing.cell_padding)),
+    );
+    layout.push(
+        elements::Paragraph::new(text).styled(
+            Style::new()
+                .with_font_size(theme.typography.small)
+                .with_color(theme.palette.text.into()),
+        ),
+    );
+    elements::FramedElement::new(layout.padded(Margins::all(theme.spacing.cell_padding)))
+        .styled(accent)
+}
+
+/// Bens e Direitos section for one asset in the situation at the end of `period`: heading with
+/// the code for `tax_year`, reference date, and the Fonte/Saldo/Custo/Preço table of
+/// `statement`, opened with `opening`, next to the `discriminacao` box. `title` is shown above
+/// the first section of a page.
+#[allow(clippy::too_many_arguments)]
+pub fn asset_section(
+    theme: &Theme,
+    title: Option<&str>,
+    asset: &AssetDescriptor,
+    tax_year: TaxYear,
+    period: Period,
+    statement: &Statement,
+    opening: Option<&Total>,
+    discriminacao: &str,
+) -> Result<LinearLayout, Error> {
+    let date = elements::Paragraph::default()
+        .styled_string("Data:", Color::from(theme.palette.muted))
+        .string(format!(" {}", tax_year.closing_date(period)))
+        .styled(Style::new().with_font_size(theme.typography.body))
+        .styled(Color::from(theme.palette.text));
+    let heading = asset.heading(tax_year);
+    let layout = asset_linear_layout(theme, title, &heading, asset.accent(), date);
+
+    let mut section = elements::LinearLayout::vertical();
+    section.push(build_table(
+        vec![layout],
+        Spacing::around(theme.spacing.section_padding),
+    )?);
+
+    let mut body = elements::TableLayout::new(vec![3, 2]);
+    body.row()
+        .element(
+            asset_table(theme, asset, statement, opening)?.styled(Color::from(theme.palette.rule)),
+        )
+        .element(
+            discriminacao_box(theme, asset.accent(), discriminacao).padded(Margins::trbl(
+                0,
+                0,
+                0,
+                theme.spacing.section_padding,
+            )),
+        )
+        .push()
+        .map_err(Error::InvalidTableRow)?;
+    section.push(body);
+    Ok(section)
+}
+
+/// One label/value line of a [`ficha_section`].
+fn ficha_row(theme: &Theme, label: &str, value: &str) -> Result<TableLayout, Error> {
+    let mut table = elements::TableLayout::new(vec![1, 3]);
+    let size = theme.typography.table;
+    table
+        .row()
+        .element(
+            elements::Paragraph::new(label).styled(
+                Style::new()
+                    .with_color(theme.palette.muted.into())
+                    .with_font_size(size),
+            ),
+        )
+        .element(
+            elements::Paragraph::new(value).styled(
+                Style::new()
+                    .with_color(theme.palette.text.into())
+                    .with_font_size(size),
+            ),
+        )
+        .push()
+        .map_err(Error::InvalidTableRow)?;
+    Ok(table)
+}
+
+/// The Bens e Direitos ficha of one asset, with exactly the fields of the IRPF program so that
+/// it can be filled in field by field. `title` is shown above the first ficha of a page.
+pub fn ficha_section(
+    theme: &Theme,
+    title: Option<&str>,
+    asset: &AssetDescriptor,
+    tax_year: TaxYear,
+    ficha: &Ficha,
+) -> Result<LinearLayout, Error> {
+    let situacao = |period| format!("Situação em {}", tax_year.closing_date(period));
+    let rows = vec![
+        ficha_row(
+            theme,
+            "Grupo",
+            &ficha
+                .code
+                .group_label()
+                .unwrap_or_else(|| "Não se aplica".to_string()),
+        )?,
+        ficha_row(theme, "Código", &ficha.code.code_label())?,
+        ficha_row(theme, "Localização (país)", ficha.localizacao)?,
+        ficha_row(theme, "CNPJ", &ficha.cnpj.to_string())?,
+        ficha_row(theme, "Discriminação", &ficha.discriminacao)?,
+        ficha_row(
+            theme,
+            &situacao(Period::Previous),
+            &ficha.situacao_anterior.to_string(),
+        )?,
+        ficha_row(
+            theme,
+            &situacao(Period::Current),
+            &ficha.situacao_atual.to_string(),
+        )?,
+    ];
+    let mut table = build_table(rows, Spacing::below(theme.spacing.row_padding))?;
+    add_empty_row(&mut table)?;
+
+    let heading = asset.heading(tax_year);
+    let layout = asset_linear_layout(
+        theme,
+        title,
+        &heading,
+        asset.accent(),
+        elements::Break::new(0),
+    );
+    let mut section = elements::LinearLayout::vertical();
+    section.push(build_table(
+        vec![layout],
+        Spacing::around(theme.spacing.section_padding),
+    )?);
+    section.push(table.styled(Color::from(theme.palette.rule)));
+    Ok(section)
+}
@@ -0,0 +1,30 @@
+//! Generates the Informe de Rendimentos Financeiro: the yearly statement of fixed income and
+//! crypto asset positions customers use to fill in their IRPF declaration.
+//!
+//! A [`Report`] is usually decoded with [`input::load`], then rendered with [`render`] into any
+//! [`std::io::Write`], with [`render_to_vec`] into memory or with [`render_to_file`].
+//! [`build_document`] returns the [`genpdf::Document`] itself, and the [`layout`] module exposes
+//! the building blocks it is made of.
+
+pub mod asset;
+pub mod assets;
+pub mod batch;
+pub mod cost;
+mod error;
+pub mod input;
+pub mod layout;
+pub mod masking;
+pub mod model;
+pub mod money;
+pub mod page;
+pub mod quantity;
+pub mod receita;
+mod render;
+pub mod tax_id;
+pub mod tax_year;
+pub mod tenant;
+pub mod theme;
+
+pub use error::Error;
+pub use model::Report;
+pub use render::{build_document, render, render_to_file, render_to_vec, RenderOptions, Renderer};
@@ -1,614 +1,686 @@
-use genpdf::elements::{LinearLayout, PageBreak, TableLayout};
-use genpdf::style::Style;
-use genpdf::Scale;
-use genpdf::{elements, fonts, style, Alignment, Element, Margins, Mm};
-
-struct RowData {
-    text: String,
-    style: Style,
-    alignment: Alignment,
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use chrono::Datelike;
+use clap::{Args, Parser, Subcommand};
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::value::RawValue;
+
+use pdflover::batch::{self, Job};
+use pdflover::cost::{self, CostMethod};
+use pdflover::input::{self, InputError, Loaded, TaxIdPolicy};
+use pdflover::money::Money;
+use pdflover::quantity::Quantity;
+use pdflover::tax_year::TaxYear;
+use pdflover::tenant::Tenant;
+use pdflover::theme::Theme;
+use pdflover::{RenderOptions, Renderer, Report};
+
+#[derive(Parser)]
+#[command(version, about = "Generates Informe de Rendimentos PDFs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-impl RowData {
-    fn new(text: &str, style: Style, alignment: Alignment) -> Self {
-        Self {
-            text: text.to_string(),
-            style,
-            alignment,
+#[derive(Subcommand)]
+enum Command {
+    /// Renders one JSON input into a PDF.
+    Render {
+        input: PathBuf,
+        /// Defaults to the input path with a `.pdf` extension. `-` writes to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Checks inputs against the tenant they would be rendered for, without rendering them.
+    /// Exits with a non-zero status if any is invalid.
+    Validate {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Replays the ledgers of an input with every cost method and prints the results side by
+    /// side, with the difference between them.
+    Compare {
+        input: PathBuf,
+        #[command(flatten)]
+        options: Options,
+    },
+    /// Renders a directory of JSON inputs, or a JSON Lines stream with one request per line.
+    ///
+    /// A line is either a report input or `{"id": ..., "output": ..., "report": ...}`, where
+    /// `output` is optional. Writes one JSON line per report to stdout as they finish, with its
+    /// `id`, `output`, `sha256`, `pages` and `error`; only `id` and `error` are set on failure.
+    Batch {
+        /// A directory, a JSON Lines file or `-` for stdin.
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Number of reports rendered in parallel. Defaults to one per CPU core.
+        #[arg(short, long)]
+        jobs: Option<NonZeroUsize>,
+        #[command(flatten)]
+        options: Options,
+    },
+}
+
+#[derive(Args)]
+struct Options {
+    /// Directory holding the Roboto font family. Defaults to the copy built into the binary.
+    #[arg(long)]
+    fonts: Option<PathBuf>,
+    /// TOML profile of the brand the reports are issued under. Defaults to Bipa.
+    #[arg(long)]
+    tenant: Option<PathBuf>,
+    /// Image shown at the top of the first page, overriding the tenant's.
+    #[arg(long)]
+    logo: Option<PathBuf>,
+    /// TOML file with the colors, font sizes and spacing of the report, overriding the tenant's.
+    #[arg(long)]
+    theme: Option<PathBuf>,
+    /// Hide the beneficiary's CPF, agência and conta as the tenant's masking policy says.
+    #[arg(long)]
+    masked: bool,
+    /// Overrides the `tax_year` of every input.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(2000..))]
+    tax_year: Option<u16>,
+    /// Overrides the `cost_method` of every input: `average` or `fifo`.
+    #[arg(long)]
+    cost_method: Option<CostMethod>,
+    /// Report invalid CPF/CNPJ check digits as warnings instead of errors.
+    #[arg(long)]
+    warn_invalid_tax_ids: bool,
+}
+
+impl Options {
+    fn policy(&self) -> TaxIdPolicy {
+        if self.warn_invalid_tax_ids {
+            TaxIdPolicy::Warn
+        } else {
+            TaxIdPolicy::Reject
+        }
+    }
+
+    /// Builds the render options, exiting if the tenant or theme can't be loaded.
+    fn render_options(&self) -> RenderOptions {
+        fn load<T>(path: &Path, load: fn(&Path) -> Result<T, InputError>) -> T {
+            load(path).unwrap_or_else(|err| {
+                eprintln!("{}: {}", path.display(), err);
+                process::exit(1);
+            })
+        }
+
+        let mut tenant = match &self.tenant {
+            Some(path) => load(path, Tenant::load),
+            None => Tenant::bipa(),
+        };
+        if let Some(logo) = &self.logo {
+            tenant.logo = Some(logo.clone());
+        }
+        if let Some(path) = &self.theme {
+            tenant.theme = load(path, Theme::load);
+        }
+        RenderOptions {
+            fonts_dir: self.fonts.clone(),
+            tenant,
+            issue: None,
+            masked: self.masked,
         }
     }
-}
 
-fn add_empty_row(table: &mut TableLayout) {
-    (*table)
-        .row()
-        .element(elements::Break::new(0))
-        .push()
-        .unwrap();
+    /// Applies the command-line overrides and prints the warnings of a loaded input.
+    fn prepare(&self, name: &str, loaded: Loaded) -> Result<Report, InputError> {
+        for warning in &loaded.warnings {
+            eprintln!("{}: warning: {}", name, warning);
+        }
+        let mut report = loaded.report;
+        self.overrides().apply(&mut report)?;
+        Ok(report)
+    }
+
+    fn overrides(&self) -> Overrides {
+        Overrides {
+            tax_year: self.tax_year.map(TaxYear::new),
+            cost_method: self.cost_method,
+        }
+    }
 }
 
-fn build_table<E: Element + 'static>(data: Vec<E>, padding: Margins) -> TableLayout {
-    let mut table = elements::TableLayout::new(vec![1]);
-    table.set_cell_decorator(elements::FrameCellDecorator::new(true, false, false));
+/// The command-line options that change the reports themselves.
+#[derive(Clone, Copy)]
+struct Overrides {
+    tax_year: Option<TaxYear>,
+    cost_method: Option<CostMethod>,
+}
 
-    for d in data {
-        table.row().element(d.padded(padding)).push().unwrap();
+impl Overrides {
+    /// Sets the overridden fields, replaying the ledgers again if any changed.
+    fn apply(self, report: &mut Report) -> Result<(), InputError> {
+        if let Some(year) = self.tax_year {
+            report.tax_year = year;
+        }
+        if let Some(method) = self.cost_method {
+            report.cost_method = method;
+        }
+        if self.tax_year.is_some() || self.cost_method.is_some() {
+            input::apply_ledgers(report)?;
+        }
+        Ok(())
     }
+}
+
+/// A JSON Lines batch entry that names its report and, optionally, where to write it.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    id: String,
+    output: Option<PathBuf>,
+    report: Box<RawValue>,
+}
 
-    table
+/// One input of a batch: a name used for messages and the output file, and its source.
+enum BatchItem {
+    File(PathBuf),
+    Line(usize, String),
+    Request(usize, Request),
+    /// A line rejected before loading, with the `id` it came with, if any.
+    Invalid {
+        line: usize,
+        id: Option<String>,
+        error: InputError,
+    },
 }
 
-fn row_table(data: Vec<RowData>) -> elements::TableLayout {
-    let mut table = elements::TableLayout::new(data.iter().map(|_| 1).collect());
-    table.set_cell_decorator(elements::FrameCellDecorator::new(false, false, false));
-    let vertical_padding = Mm::from(2);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-
-    let mut row = table.row();
-    for d in data {
-        row = row.element(
-            elements::Paragraph::new(d.text)
-                .aligned(d.alignment)
-                .styled(d.style)
-                .padded(padding),
-        )
+/// Just the `id` of a line. Reports have no `id`, so a line with one is always a request.
+#[derive(Deserialize)]
+struct Tagged {
+    id: Option<String>,
+}
+
+impl BatchItem {
+    fn parse(number: usize, line: &str) -> Self {
+        let id = serde_json::from_str::<Tagged>(line)
+            .ok()
+            .and_then(|tagged| tagged.id);
+        let Some(id) = id else {
+            return BatchItem::Line(number, line.to_string());
+        };
+        let invalid = |field: &str, reason: String| BatchItem::Invalid {
+            line: number,
+            id: Some(id.clone()),
+            error: InputError::Invalid {
+                field: field.to_string(),
+                reason,
+            },
+        };
+
+        let request = match serde_json::from_str::<Request>(line) {
+            Ok(request) => request,
+            Err(err) => return invalid("<request>", err.to_string()),
+        };
+        let path = Path::new(&request.id);
+        if request.output.is_none() && path.file_name() != Some(path.as_os_str()) {
+            let reason = "must be a file name, or come with an `output`";
+            return invalid("id", reason.to_string());
+        }
+        BatchItem::Request(number, request)
+    }
+
+    fn name(&self) -> String {
+        match self {
+            BatchItem::File(path) => path.file_stem().map_or_else(
+                || "report".to_string(),
+                |stem| stem.to_string_lossy().into(),
+            ),
+            BatchItem::Line(number, _) => format!("line-{}", number),
+            BatchItem::Request(_, request) => request.id.clone(),
+            BatchItem::Invalid { line, id, .. } => {
+                id.clone().unwrap_or_else(|| format!("line-{}", line))
+            }
+        }
+    }
+
+    fn output(&self, dir: &Path) -> PathBuf {
+        match self {
+            BatchItem::Request(
+                _,
+                Request {
+                    output: Some(output),
+                    ..
+                },
+            ) => output.clone(),
+            // Never written, but kept inside `dir` whatever the rejected `id` was.
+            BatchItem::Invalid { line, .. } => dir.join(format!("line-{}.pdf", line)),
+            _ => dir.join(format!("{}.pdf", self.name())),
+        }
     }
-    row.push().unwrap();
 
-    table
+    fn load(self, policy: TaxIdPolicy) -> Result<Loaded, InputError> {
+        match self {
+            BatchItem::File(path) => input::load(&path, policy),
+            BatchItem::Line(_, json) => input::from_json(&json, policy),
+            BatchItem::Request(_, request) => input::from_json(request.report.get(), policy),
+            BatchItem::Invalid { error, .. } => Err(error),
+        }
+    }
 }
 
-fn linear_layout<E: Element + 'static>(title: &str, subtitle: &str, element: E) -> LinearLayout {
-    let mut layout = elements::LinearLayout::vertical();
-    let bipa_color = style::Color::Rgb(0, 206, 120);
-    let vertical_padding = Mm::from(2);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let black = style::Color::Rgb(28, 28, 30);
-
-    layout.push(
-        elements::Paragraph::new(title)
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(12))
-            .styled(bipa_color)
-            .padded(padding),
-    );
-
-    layout.push(
-        elements::Paragraph::new(subtitle)
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(12))
-            .padded(padding)
-            .styled(black),
-    );
-
-    layout.push(element);
-
-    layout
+/// Pairs each item with its output path, rejecting lines whose path an earlier line already
+/// claimed: both would be reported as rendered, but only the last PDF would remain.
+fn claim_outputs(
+    items: impl Iterator<Item = BatchItem>,
+    dir: &Path,
+) -> impl Iterator<Item = (BatchItem, PathBuf)> {
+    let dir = dir.to_path_buf();
+    let mut claimed = HashSet::new();
+    items.map(move |item| {
+        let output = item.output(&dir);
+        let (line, id) = match &item {
+            BatchItem::Line(line, _) => (*line, None),
+            BatchItem::Request(line, request) => (*line, Some(request.id.clone())),
+            BatchItem::File(_) | BatchItem::Invalid { .. } => return (item, output),
+        };
+        if claimed.insert(output.clone()) {
+            return (item, output);
+        }
+        let item = BatchItem::Invalid {
+            line,
+            id,
+            error: InputError::Invalid {
+                field: "output".to_string(),
+                reason: format!(
+                    "duplicate id/output, {} is already written for an earlier line",
+                    output.display()
+                ),
+            },
+        };
+        let output = item.output(&dir);
+        (item, output)
+    })
 }
 
-fn btc_linear_layout<E: Element + 'static>(
-    title: &str,
-    subtitle: &str,
-    element: E,
-) -> LinearLayout {
-    let mut layout = elements::LinearLayout::vertical();
-    let btc_color = style::Color::Rgb(68, 87, 212);
-    let vertical_padding = Mm::from(2);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let black = style::Color::Rgb(28, 28, 30);
-
-    layout.push(
-        elements::Paragraph::new(title)
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(14))
-            .styled(black)
-            .padded(padding),
-    );
-
-    layout.push(
-        elements::Paragraph::new(subtitle)
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(12))
-            .padded(padding)
-            .styled(btc_color),
-    );
-
-    layout.push(element);
-
-    layout
+/// The items of a JSON Lines stream, parsed as they are read.
+struct LineItems<R> {
+    lines: io::Lines<R>,
+    path: PathBuf,
+    number: usize,
+    done: bool,
 }
 
-fn gold_linear_layout<E: Element + 'static>(subtitle: &str, element: E) -> LinearLayout {
-    let mut layout = elements::LinearLayout::vertical();
-    let color = style::Color::Rgb(255, 204, 0);
-    let vertical_padding = Mm::from(2);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let black = style::Color::Rgb(28, 28, 30);
-
-    layout.push(
-        elements::Paragraph::new(subtitle)
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(12))
-            .styled(color)
-            .padded(padding),
-    );
-
-    layout.push(element);
-
-    layout
+impl<R: BufRead> Iterator for LineItems<R> {
+    type Item = BatchItem;
+
+    fn next(&mut self) -> Option<BatchItem> {
+        while !self.done {
+            let line = self.lines.next()?;
+            self.number += 1;
+            match line {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => return Some(BatchItem::parse(self.number, &line)),
+                Err(source) => {
+                    // A line that isn't UTF-8 is skipped; any other failure ends the stream.
+                    self.done = source.kind() != io::ErrorKind::InvalidData;
+                    return Some(BatchItem::Invalid {
+                        line: self.number,
+                        id: None,
+                        error: InputError::Io {
+                            path: self.path.clone(),
+                            source,
+                        },
+                    });
+                }
+            }
+        }
+        None
+    }
 }
 
-fn pdf() {
-    let default_font = fonts::from_files("assets/Roboto", "Roboto", None)
-        .expect("Failed to load the default font family");
-
-    let mut doc = genpdf::Document::new(default_font);
-    doc.set_minimal_conformance();
-    doc.set_line_spacing(1.25);
-
-    let mut decorator = genpdf::SimplePageDecorator::new();
-    decorator.set_margins(10);
-    decorator.set_header(|page| {
-        let mut layout = elements::LinearLayout::vertical();
-        if page > 1 {
-            layout.push(
-                elements::Paragraph::new(format!("Página {}", page)).aligned(Alignment::Center),
-            );
-            layout.push(elements::Break::new(1));
+/// The items of a directory of JSON inputs, or of a JSON Lines file or stdin (`-`), read
+/// lazily line by line.
+fn batch_items(input: &Path) -> io::Result<Box<dyn Iterator<Item = BatchItem> + Send>> {
+    let reader: Box<dyn BufRead + Send> = if input == Path::new("-") {
+        Box::new(BufReader::new(io::stdin()))
+    } else if input.is_dir() {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(input)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                paths.push(path);
+            }
         }
-        layout.styled(style::Style::new().with_font_size(10))
-    });
-    doc.set_page_decorator(decorator);
-
-    let mut table = elements::TableLayout::new(vec![1, 1]);
-    let mut image = elements::Image::from_path("assets/bipa-logo.jpg").unwrap();
-    image.set_scale(Scale::new(0.3, 0.3));
-    image.set_alignment(Alignment::Left);
-
-    let gold_color = style::Color::Rgb(255, 204, 0);
-    let bipa_color = style::Color::Rgb(0, 206, 120);
-    let btc_color = style::Color::Rgb(68, 87, 212);
-    let gray4 = style::Color::Rgb(174, 174, 178);
-    let black = style::Color::Rgb(28, 28, 30);
-
-    table
-        .row()
-        .element(image)
-        .element(
-            elements::Paragraph::new("Informe de Rendimentos Financeiro")
-                .aligned(Alignment::Right)
-                .styled(style::Style::new().bold().with_font_size(14))
-                .styled(bipa_color),
-        )
-        .push()
-        .expect("Invalid table row");
-
-    doc.push(table);
-
-    doc.push(elements::Break::new(1.0));
-
-    doc.push(
-        elements::Paragraph::new("Imposto de Renda - Pessoa Física")
-            .aligned(Alignment::Left)
-            .styled(style::Style::new().bold().with_font_size(16)),
-    );
-
-    let layout = linear_layout(
-        "Idenficação da Fonte Pagadora",
-        "Acesso Soluções de Pagamento S.A.",
-        elements::Paragraph::default()
-            .styled_string("CNPJ:", gray4)
-            .string(" 13.140.088/0001-99")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let layout1 = linear_layout(
-        "Pessoa Física Beneficiária dos Rendimentos",
-        "Felipe Rosa",
-        elements::Paragraph::default()
-            .styled_string("CPF:", gray4)
-            .string(" 000.000.000-00")
-            .styled_string("    Agência:", gray4)
-            .string(" 0001")
-            .styled_string("    Conta:", gray4)
-            .string(" 0020332")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout, layout1];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let mut table = build_table(rows, padding);
-    add_empty_row(&mut table);
-
-    let gray3 = style::Color::Rgb(199, 199, 204);
-    let table = table.styled(gray3);
-    doc.push(elements::Break::new(1.0));
-    doc.push(table);
-    doc.push(elements::Break::new(1));
-
-    let data1 = vec![
-        RowData::new(
-            "Rendimentos Sujeitos a Tributação Exclusiva",
-            Style::new().with_color(black).with_font_size(12).bold(),
-            Alignment::Left,
-        ),
-        RowData::new(
-            "Valores em R$",
-            Style::new().with_color(gray3).with_font_size(10),
-            Alignment::Right,
-        ),
-    ];
-    let row1 = row_table(data1);
-
-    let leading_row_style = Style::new().with_color(gray4).with_font_size(10);
-    let trailing_row_style = Style::new().with_color(black).with_font_size(10).bold();
-    let data2 = vec![
-        RowData::new("Saldo em 31/12/2020", leading_row_style, Alignment::Left),
-        RowData::new("R$0", trailing_row_style, Alignment::Right),
-    ];
-    let row2 = row_table(data2);
-
-    let data3 = vec![
-        RowData::new("Saldo em 31/12/2021", leading_row_style, Alignment::Left),
-        RowData::new("R$0", trailing_row_style, Alignment::Right),
-    ];
-    let row3 = row_table(data3);
-
-    let leading_row_style = Style::new().with_color(bipa_color).with_font_size(8);
-    let trailing_row_style = Style::new()
-        .with_color(bipa_color)
-        .with_font_size(10)
-        .bold();
-    let data4 = vec![
-        RowData::new("Rendimentos Líquidos", leading_row_style, Alignment::Left),
-        RowData::new("R$0", trailing_row_style, Alignment::Right),
-    ];
-    let row4 = row_table(data4);
-
-    let vertical_padding = Mm::from(3);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-    let rows = vec![row1, row2, row3, row4];
-    let table = build_table(rows, padding);
-    let table = table.styled(gray3);
-
-    doc.push(table);
-
-    doc.push(elements::PageBreak::new());
-
-    let layout = linear_layout(
-        "Idenficação da Fonte Compradora e Custodiante de Criptoativos",
-        "Bipa Intermediação de Ativos Digitais LTDA",
-        elements::Paragraph::default()
-            .styled_string("CNPJ:", gray4)
-            .string(" 37.008.710/0001-78")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let mut table = build_table(rows, padding);
-    add_empty_row(&mut table);
-
-    let table = table.styled(gray3);
-    doc.push(table);
-
-    let layout = btc_linear_layout(
-        "Bens e Direitos",
-        "81 - Criptoativo Bitcoin - BTC",
-        elements::Paragraph::default()
-            .styled_string("Data:", gray4)
-            .string(" 31/12/2021")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let table = build_table(rows, padding);
-    doc.push(table);
-
-    let table_header_style = Style::new().with_color(gray3).with_font_size(10);
-    let table_value = Style::new().with_color(black).with_font_size(10);
-    let total_style = Style::new().with_color(btc_color).with_font_size(10);
-
-    let data1 = vec![
-        RowData::new("Fonte", table_header_style, Alignment::Left),
-        RowData::new("Saldo", table_header_style, Alignment::Left),
-        RowData::new("Custo", table_header_style, Alignment::Left),
-        RowData::new("Preço", table_header_style, Alignment::Left),
-    ];
-
-    let row1 = row_table(data1);
-
-    let data2 = vec![
-        RowData::new("Comprado", table_value, Alignment::Left),
-        RowData::new("0,5 BTC", table_value, Alignment::Left),
-        RowData::new("R$0,0535", table_value, Alignment::Left),
-        RowData::new("R$205.000", table_value, Alignment::Left),
-    ];
-
-    let row2 = row_table(data2);
-
-    let data3 = vec![
-        RowData::new("Vendido", table_value, Alignment::Left),
-        RowData::new("-0,2 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row3 = row_table(data3);
-
-    let data4 = vec![
-        RowData::new("Depositado", table_value, Alignment::Left),
-        RowData::new("0,1 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row4 = row_table(data4);
-
-    let data5 = vec![
-        RowData::new("Sacado", table_value, Alignment::Left),
-        RowData::new("-0,1 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row5 = row_table(data5);
-
-    let total_data = vec![
-        RowData::new("Total", total_style, Alignment::Left),
-        RowData::new("0,3 BTC", total_style, Alignment::Left),
-        RowData::new("R$0,0535", total_style, Alignment::Left),
-        RowData::new("R$205.000", total_style, Alignment::Left),
-    ];
-
-    let total = row_table(total_data);
-
-    let vertical_padding = Mm::from(3);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let rows = vec![row1, row2, row3, row4, row5, total];
-    let table = build_table(rows, padding);
-    let table = table.styled(gray3);
-
-    doc.push(table);
-
-    let layout = gold_linear_layout(
-        "89 - Outros criptoativos (PAX Gold - PAXG)",
-        elements::Paragraph::default()
-            .styled_string("Data:", gray4)
-            .string(" 31/12/2021")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let table = build_table(rows, padding);
-    doc.push(table);
-
-    let table_header_style = Style::new().with_color(gray3).with_font_size(10);
-    let table_value = Style::new().with_color(black).with_font_size(10);
-    let total_style = Style::new().with_color(gold_color).with_font_size(10);
-
-    let data1 = vec![
-        RowData::new("Fonte", table_header_style, Alignment::Left),
-        RowData::new("Saldo", table_header_style, Alignment::Left),
-        RowData::new("Custo", table_header_style, Alignment::Left),
-        RowData::new("Preço", table_header_style, Alignment::Left),
-    ];
-
-    let row1 = row_table(data1);
-
-    let data2 = vec![
-        RowData::new("Comprado", table_value, Alignment::Left),
-        RowData::new("0,5 PAXG", table_value, Alignment::Left),
-        RowData::new("R$0,0535", table_value, Alignment::Left),
-        RowData::new("R$205.000", table_value, Alignment::Left),
-    ];
-
-    let row2 = row_table(data2);
-
-    let data3 = vec![
-        RowData::new("Vendido", table_value, Alignment::Left),
-        RowData::new("-0,2 PAXG", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row3 = row_table(data3);
-
-    let total_data = vec![
-        RowData::new("Total", total_style, Alignment::Left),
-        RowData::new("0,3 PAXG", total_style, Alignment::Left),
-        RowData::new("R$0,0535", total_style, Alignment::Left),
-        RowData::new("R$205.000", total_style, Alignment::Left),
-    ];
-
-    let total = row_table(total_data);
-
-    let vertical_padding = Mm::from(3);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let rows = vec![row1, row2, row3, total];
-    let table = build_table(rows, padding);
-    let table = table.styled(gray3);
-
-    doc.push(table);
-
-    doc.push(PageBreak::new());
-
-    let layout = btc_linear_layout(
-        "Bens e Direitos",
-        "81 - Criptoativo Bitcoin - BTC",
-        elements::Paragraph::default()
-            .styled_string("Data:", gray4)
-            .string(" 31/12/2020")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let table = build_table(rows, padding);
-    doc.push(table);
-
-    let table_header_style = Style::new().with_color(gray3).with_font_size(10);
-    let table_value = Style::new().with_color(black).with_font_size(10);
-    let total_style = Style::new().with_color(btc_color).with_font_size(10);
-
-    let data1 = vec![
-        RowData::new("Fonte", table_header_style, Alignment::Left),
-        RowData::new("Saldo", table_header_style, Alignment::Left),
-        RowData::new("Custo", table_header_style, Alignment::Left),
-        RowData::new("Preço", table_header_style, Alignment::Left),
-    ];
-
-    let row1 = row_table(data1);
-
-    let data2 = vec![
-        RowData::new("Comprado", table_value, Alignment::Left),
-        RowData::new("0,5 BTC", table_value, Alignment::Left),
-        RowData::new("R$0,0535", table_value, Alignment::Left),
-        RowData::new("R$205.000", table_value, Alignment::Left),
-    ];
-
-    let row2 = row_table(data2);
-
-    let data3 = vec![
-        RowData::new("Vendido", table_value, Alignment::Left),
-        RowData::new("-0,2 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row3 = row_table(data3);
-
-    let data4 = vec![
-        RowData::new("Depositado", table_value, Alignment::Left),
-        RowData::new("0,1 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row4 = row_table(data4);
-
-    let data5 = vec![
-        RowData::new("Sacado", table_value, Alignment::Left),
-        RowData::new("-0,1 BTC", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row5 = row_table(data5);
-
-    let total_data = vec![
-        RowData::new("Total", total_style, Alignment::Left),
-        RowData::new("0,3 BTC", total_style, Alignment::Left),
-        RowData::new("R$0,0535", total_style, Alignment::Left),
-        RowData::new("R$205.000", total_style, Alignment::Left),
-    ];
-
-    let total = row_table(total_data);
-
-    let vertical_padding = Mm::from(3);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let rows = vec![row1, row2, row3, row4, row5, total];
-    let table = build_table(rows, padding);
-    let table = table.styled(gray3);
-
-    doc.push(table);
-
-    let layout = gold_linear_layout(
-        "89 - Outros criptoativos (PAX Gold - PAXG)",
-        elements::Paragraph::default()
-            .styled_string("Data:", gray4)
-            .string(" 31/12/2020")
-            .styled(style::Style::new().with_font_size(12))
-            .styled(black),
-    );
-
-    let rows = vec![layout];
-    let vertical_padding = Mm::from(5);
-    let zero = Mm::from(0);
-    let padding = Margins::trbl(vertical_padding, zero, vertical_padding, zero);
-
-    let table = build_table(rows, padding);
-    doc.push(table);
-
-    let table_header_style = Style::new().with_color(gray3).with_font_size(10);
-    let table_value = Style::new().with_color(black).with_font_size(10);
-    let total_style = Style::new().with_color(gold_color).with_font_size(10);
-
-    let data1 = vec![
-        RowData::new("Fonte", table_header_style, Alignment::Left),
-        RowData::new("Saldo", table_header_style, Alignment::Left),
-        RowData::new("Custo", table_header_style, Alignment::Left),
-        RowData::new("Preço", table_header_style, Alignment::Left),
-    ];
-
-    let row1 = row_table(data1);
-
-    let data2 = vec![
-        RowData::new("Comprado", table_value, Alignment::Left),
-        RowData::new("0,5 PAXG", table_value, Alignment::Left),
-        RowData::new("R$0,0535", table_value, Alignment::Left),
-        RowData::new("R$205.000", table_value, Alignment::Left),
-    ];
-
-    let row2 = row_table(data2);
-
-    let data3 = vec![
-        RowData::new("Vendido", table_value, Alignment::Left),
-        RowData::new("-0,2 PAXG", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-        RowData::new("N/A", table_value, Alignment::Left),
-    ];
-
-    let row3 = row_table(data3);
-
-    let total_data = vec![
-        RowData::new("Total", total_style, Alignment::Left),
-        RowData::new("0,3 PAXG", total_style, Alignment::Left),
-        RowData::new("R$0,0535", total_style, Alignment::Left),
-        RowData::new("R$205.000", total_style, Alignment::Left),
-    ];
-
-    let total = row_table(total_data);
-
-    let vertical_padding = Mm::from(3);
-    let padding = Margins::trbl(zero, zero, vertical_padding, zero);
-    let rows = vec![row1, row2, row3, total];
-    let table = build_table(rows, padding);
-    let table = table.styled(gray3);
-
-    doc.push(table);
-
-    doc.render_to_file("test_working.pdf")
-        .expect("Failed to write output file");
+        paths.sort();
+        return Ok(Box::new(paths.into_iter().map(BatchItem::File)));
+    } else {
+        Box::new(BufReader::new(fs::File::open(input)?))
+    };
+    Ok(Box::new(LineItems {
+        lines: reader.lines(),
+        path: input.to_path_buf(),
+        number: 0,
+        done: false,
+    }))
 }
 
 fn main() {
-    pdf()
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Render {
+            input,
+            output,
+            options,
+        } => {
+            let render_options = options.render_options();
+            let name = input.display().to_string();
+            let report = match input::load(&input, options.policy())
+                .and_then(|loaded| options.prepare(&name, loaded))
+            {
+                Ok(report) => report,
+                Err(err) => {
+                    eprintln!("{}: {}", name, err);
+                    process::exit(1);
+                }
+            };
+            let output = output.unwrap_or_else(|| input.with_extension("pdf"));
+            let result = if output == Path::new("-") {
+                pdflover::render(&report, &render_options, io::stdout().lock())
+            } else {
+                pdflover::render_to_file(&report, &render_options, &output)
+            };
+            if let Err(err) = result {
+                eprintln!("{}: {}", input.display(), err);
+                process::exit(1);
+            }
+        }
+        Command::Validate { inputs, options } => {
+            // Loads the tenant, theme, logo and fonts just as rendering would.
+            let renderer = match Renderer::new(options.render_options()) {
+                Ok(renderer) => renderer,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            };
+            let mut failed = false;
+            for input in &inputs {
+                let name = input.display().to_string();
+                let result = input::load(input, options.policy())
+                    .and_then(|loaded| options.prepare(&name, loaded))
+                    .map_err(pdflover::Error::Input)
+                    .and_then(|report| renderer.options().check(&report));
+                if let Err(err) = result {
+                    eprintln!("{}: {}", name, err);
+                    failed = true;
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Command::Compare { input, options } => {
+            let name = input.display().to_string();
+            let result = input::load(&input, options.policy())
+                .and_then(|loaded| options.prepare(&name, loaded))
+                .and_then(|report| compare(&report));
+            if let Err(err) = result {
+                eprintln!("{}: {}", name, err);
+                process::exit(1);
+            }
+        }
+        Command::Batch {
+            input,
+            output,
+            jobs: jobs_arg,
+            options,
+        } => {
+            let items = match batch_items(&input) {
+                Ok(items) => items,
+                Err(err) => {
+                    eprintln!("could not read {}: {}", input.display(), err);
+                    process::exit(1);
+                }
+            };
+            if let Err(err) = fs::create_dir_all(&output) {
+                eprintln!("could not create {}: {}", output.display(), err);
+                process::exit(1);
+            }
+
+            let renderer = match Renderer::new(options.render_options()) {
+                Ok(renderer) => renderer,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            };
+            let policy = options.policy();
+            let overrides = options.overrides();
+            let jobs = claim_outputs(items, &output).map(|(item, path)| {
+                let name = item.name();
+                Job::new(name, path, move || {
+                    let mut loaded = item.load(policy)?;
+                    overrides.apply(&mut loaded.report)?;
+                    Ok(loaded)
+                })
+            });
+
+            let workers = jobs_arg.map_or_else(batch::default_workers, NonZeroUsize::get);
+            let summary = batch::run(&renderer, jobs, workers, |outcome| {
+                for warning in &outcome.warnings {
+                    eprintln!("{}: warning: {}", outcome.name, warning);
+                }
+                let (sha256, pages, error) = match &outcome.result {
+                    Ok(rendered) => {
+                        eprintln!("{}: rendered in {:.2?}", outcome.name, outcome.duration);
+                        (Some(&rendered.sha256), Some(rendered.pages), None)
+                    }
+                    Err(err) => {
+                        eprintln!("{}: {}", outcome.name, err);
+                        (None, None, Some(err.to_string()))
+                    }
+                };
+                let result = json!({
+                    "id": outcome.name,
+                    "output": outcome.result.is_ok().then_some(&outcome.output),
+                    "sha256": sha256,
+                    "pages": pages,
+                    "error": error,
+                });
+                println!("{}", result);
+            });
+
+            let failed = summary.failed();
+            eprintln!(
+                "{} rendered, {} failed in {:.2?} ({} workers, {:.2?} average, {:.2?} slowest)",
+                summary.rendered(),
+                failed,
+                summary.elapsed,
+                workers,
+                summary.average(),
+                summary.slowest(),
+            );
+            if failed > 0 {
+                eprintln!("failures:");
+                for (outcome, err) in summary.failures() {
+                    eprintln!("  {}: {}", outcome.name, err);
+                }
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Prints every position with a ledger under each [`CostMethod`]: the balance at the end of the
+/// previous year, the movements of the ano-calendário and the balance at its end.
+fn compare(report: &Report) -> Result<(), InputError> {
+    let year = report.tax_year;
+    let methods = CostMethod::ALL;
+    let mut compared = 0;
+    for (i, position) in report.positions.iter().enumerate() {
+        let Some(ledger) = &position.ledger else {
+            continue;
+        };
+        let invalid = |field: String, reason: String| InputError::Invalid { field, reason };
+        let asset = report.asset(&position.asset).ok_or_else(|| {
+            invalid(
+                format!("positions[{}].asset", i),
+                format!("unknown asset `{}`", position.asset),
+            )
+        })?;
+        let results = cost::compare(ledger, year).map_err(|err| {
+            invalid(
+                format!("positions[{}].ledger[{}]", i, err.entry),
+                err.reason,
+            )
+        })?;
+
+        if compared > 0 {
+            println!();
+        }
+        compared += 1;
+        println!("{} - {}, ano-calendário {}", asset.ticker, asset.name, year);
+        print!("{:<36}", "");
+        for method in methods {
+            print!("{:>24}", method.label());
+        }
+        println!("{:>24}", "Diferença");
+
+        let row = |label: String, costs: Vec<Money>| {
+            print!("{:<36}", label);
+            for cost in &costs {
+                print!("{:>24}", cost.to_string());
+            }
+            let difference = costs[costs.len() - 1].amount() - costs[0].amount();
+            println!("{:>24}", Money::from(difference).to_string());
+        };
+        let quantity = |quantity: Quantity| quantity.format(&asset);
+
+        if let Some(previous) = &results[0].1.previous {
+            let costs = results
+                .iter()
+                .map(|(_, statements)| {
+                    statements
+                        .previous
+                        .as_ref()
+                        .map_or(Money::ZERO, |previous| previous.total(None).cost)
+                })
+                .collect();
+            let label = format!(
+                "31/12/{}  Saldo {}",
+                year.year() - 1,
+                quantity(previous.total(None).quantity)
+            );
+            row(label, costs);
+        }
+
+        let dates = ledger
+            .iter()
+            .filter(|entry| entry.date.year() == i32::from(year.year()))
+            .map(|entry| entry.date);
+        for (j, (date, movement)) in dates.zip(&results[0].1.current.movements).enumerate() {
+            let costs = results
+                .iter()
+                .map(|(_, statements)| {
+                    statements.current.movements[j]
+                        .signed_cost()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let label = format!(
+                "{}  {} {}",
+                date.format("%d/%m/%Y"),
+                movement.kind.label(),
+                quantity(movement.signed_quantity())
+            );
+            row(label, costs);
+        }
+
+        let current = results[0].1.current.total(None);
+        let costs = results
+            .iter()
+            .map(|(_, statements)| statements.current.total(None).cost)
+            .collect();
+        let label = format!("31/12/{}  Saldo {}", year, quantity(current.quantity));
+        row(label, costs);
+    }
+    if compared == 0 {
+        println!("No position of this report has a `ledger` to compare.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claimed(lines: &[&str]) -> Vec<(String, PathBuf, Option<String>)> {
+        let items = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| BatchItem::parse(i + 1, line));
+        claim_outputs(items, Path::new("out"))
+            .map(|(item, output)| {
+                let name = item.name();
+                let error = match item {
+                    BatchItem::Invalid { error, .. } => Some(error.to_string()),
+                    _ => None,
+                };
+                (name, output, error)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_lines_writing_to_a_claimed_output() {
+        let report = r#"{"tax_year": 2021}"#;
+        let request = |id: &str| format!(r#"{{"id": "{}", "report": {}}}"#, id, report);
+        let lines = [
+            request("x1"),
+            request("x1"),
+            report.to_string(),
+            request("line-3"),
+            format!(
+                r#"{{"id": "y", "output": "out/x1.pdf", "report": {}}}"#,
+                report
+            ),
+        ];
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let claimed = claimed(&lines);
+
+        assert_eq!(claimed[0], ("x1".into(), "out/x1.pdf".into(), None));
+        assert_eq!(claimed[1].0, "x1");
+        assert!(claimed[1]
+            .2
+            .as_ref()
+            .unwrap()
+            .contains("duplicate id/output"));
+        assert_eq!(claimed[2], ("line-3".into(), "out/line-3.pdf".into(), None));
+        assert_eq!(claimed[3].0, "line-3");
+        assert!(claimed[3].2.is_some());
+        assert_eq!(claimed[4].0, "y");
+        assert!(claimed[4].2.is_some());
+    }
+
+    #[test]
+    fn numbers_lines_past_blank_ones() {
+        let stream = "{\"id\": \"a\"}\n\n  \n{\"tax_year\": 2021}\n";
+        let items = LineItems {
+            lines: io::Cursor::new(stream).lines(),
+            path: PathBuf::from("-"),
+            number: 0,
+            done: false,
+        };
+        let names: Vec<String> = items.map(|item| item.name()).collect();
+        assert_eq!(names, ["a", "line-4"]);
+    }
 }
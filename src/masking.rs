@@ -0,0 +1,161 @@
+//! Masking of the beneficiary's personal data, for copies that leave our systems, e.g. sent by
+//! e-mail or shown in support tools. The policy lives in the [`crate::tenant::Tenant`] profile:
+//!
+//! ```toml
+//! [masking]
+//! cpf = true
+//! agency = "full"
+//! account = 3
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+
+use crate::tax_id::Cpf;
+
+/// What to hide of the beneficiary's identification.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Masking {
+    /// Prints the CPF as `***.000.000-**`.
+    pub cpf: bool,
+    pub agency: Visible,
+    pub account: Visible,
+}
+
+/// How much of the agência or conta is printed: a number of trailing characters, or `"full"`
+/// in a profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visible {
+    Full,
+    Last(usize),
+}
+
+impl<'de> Deserialize<'de> for Visible {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VisibleVisitor;
+
+        impl Visitor<'_> for VisibleVisitor {
+            type Value = Visible;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(r#"a number of trailing characters left visible, or "full""#)
+            }
+
+            fn visit_u64<E: de::Error>(self, visible: u64) -> Result<Visible, E> {
+                usize::try_from(visible)
+                    .map(Visible::Last)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(visible), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, visible: i64) -> Result<Visible, E> {
+                u64::try_from(visible)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(visible), &self))
+                    .and_then(|visible| self.visit_u64(visible))
+            }
+
+            fn visit_str<E: de::Error>(self, visible: &str) -> Result<Visible, E> {
+                match visible {
+                    "full" => Ok(Visible::Full),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(visible), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(VisibleVisitor)
+    }
+}
+
+impl Masking {
+    /// Prints everything in full.
+    pub fn none() -> Self {
+        Self {
+            cpf: false,
+            agency: Visible::Full,
+            account: Visible::Full,
+        }
+    }
+
+    pub fn cpf(&self, cpf: &Cpf) -> String {
+        if self.cpf {
+            cpf.masked()
+        } else {
+            cpf.to_string()
+        }
+    }
+
+    pub fn agency(&self, agency: &str) -> String {
+        mask(agency, self.agency)
+    }
+
+    pub fn account(&self, account: &str) -> String {
+        mask(account, self.account)
+    }
+}
+
+/// The default policy: CPF as `***.000.000-**`, agência `**01` and conta `****332`.
+impl Default for Masking {
+    fn default() -> Self {
+        Self {
+            cpf: true,
+            agency: Visible::Last(2),
+            account: Visible::Last(3),
+        }
+    }
+}
+
+/// Replaces every alphanumeric character but the last `visible` ones with `*`, keeping
+/// separators such as the `-` before a check digit.
+fn mask(value: &str, visible: Visible) -> String {
+    let Visible::Last(visible) = visible else {
+        return value.to_string();
+    };
+    let hidden = value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .count()
+        .saturating_sub(visible);
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen <= hidden {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_full_and_counts_from_a_profile() {
+        let masking: Masking = toml::from_str("agency = \"full\"\naccount = 0").unwrap();
+        assert!(masking.cpf);
+        assert_eq!(masking.agency, Visible::Full);
+        assert_eq!(masking.account, Visible::Last(0));
+        assert_eq!(masking.agency("0001"), "0001");
+        assert_eq!(masking.account("0020332"), "*******");
+
+        assert!(toml::from_str::<Masking>("agency = \"half\"").is_err());
+        assert!(toml::from_str::<Masking>("agency = -1").is_err());
+    }
+
+    #[test]
+    fn keeps_separators_visible() {
+        let masking = Masking::default();
+        assert_eq!(masking.agency("0001"), "**01");
+        assert_eq!(masking.account("002033-2"), "****33-2");
+        assert_eq!(Masking::none().account("002033-2"), "002033-2");
+    }
+}
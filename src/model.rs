@@ -0,0 +1,236 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::asset::AssetDescriptor;
+use crate::cost::{AverageCost, CostBasis, CostMethod, Holding};
+use crate::money::Money;
+use crate::quantity::Quantity;
+use crate::tax_id::{Cnpj, Cpf};
+use crate::tax_year::{Period, TaxYear};
+
+/// Everything needed to render one Informe de Rendimentos.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Report {
+    pub tax_year: TaxYear,
+    /// Paying source. Defaults to the one of the [`crate::tenant::Tenant`] the report is
+    /// rendered for.
+    pub payer: Option<Entity>,
+    pub beneficiary: Beneficiary,
+    pub fixed_income: FixedIncome,
+    /// Crypto custodian. Defaults to the one of the [`crate::tenant::Tenant`] the report is
+    /// rendered for.
+    pub custodian: Option<Entity>,
+    pub positions: Vec<AssetPosition>,
+    /// Descriptors for assets that aren't built in, or overrides for built-in ones.
+    #[serde(default)]
+    pub assets: Vec<AssetDescriptor>,
+    /// Method the statements of positions with a `ledger` are derived with.
+    #[serde(default)]
+    pub cost_method: CostMethod,
+}
+
+impl Report {
+    /// Resolves a ticker against the report's own descriptors first, then the built-in ones.
+    pub fn asset(&self, ticker: &str) -> Option<AssetDescriptor> {
+        self.assets
+            .iter()
+            .find(|asset| asset.ticker == ticker)
+            .cloned()
+            .or_else(|| AssetDescriptor::builtin(ticker))
+    }
+}
+
+/// A legal entity identified by its CNPJ, e.g. the paying source or the crypto custodian.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entity {
+    pub name: String,
+    pub cnpj: Cnpj,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Beneficiary {
+    pub name: String,
+    pub cpf: Cpf,
+    pub agency: String,
+    pub account: String,
+}
+
+/// "Rendimentos Sujeitos a Tributação Exclusiva" section.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixedIncome {
+    /// Balance on 31/12 of the previous year.
+    pub opening_balance: Money,
+    /// Balance on 31/12 of the ano-calendário.
+    pub closing_balance: Money,
+    pub net_income: Money,
+}
+
+/// One crypto asset held with the custodian.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetPosition {
+    /// Ticker of the asset, resolved through [`Report::asset`].
+    pub asset: String,
+    /// Movements during the ano-calendário.
+    #[serde(default)]
+    pub current: Statement,
+    /// Movements during the previous year, if the asset was already held then.
+    pub previous: Option<Statement>,
+    /// Every entry since the asset was first acquired, in chronological order. When present,
+    /// both statements are derived from it by [`crate::input::apply_ledgers`] instead of
+    /// being typed in.
+    pub ledger: Option<Vec<Entry>>,
+}
+
+impl AssetPosition {
+    pub fn statement(&self, period: Period) -> Option<&Statement> {
+        match period {
+            Period::Current => Some(&self.current),
+            Period::Previous => self.previous.as_ref(),
+        }
+    }
+
+    /// The balance the statement of `period` opens with: the previous year's total for the
+    /// current statement, and none for the previous one, which is taken to open empty.
+    pub fn opening(&self, period: Period) -> Option<Total> {
+        match period {
+            Period::Current => self.previous.as_ref().map(|previous| previous.total(None)),
+            Period::Previous => None,
+        }
+    }
+
+    /// The balance held at the end of `period`, or `None` without a statement for it.
+    pub fn total(&self, period: Period) -> Option<Total> {
+        let statement = self.statement(period)?;
+        Some(statement.total(self.opening(period).as_ref()))
+    }
+}
+
+/// Movements of an asset during one year, rendered as the Fonte/Saldo/Custo/Preço table. Its
+/// total is the balance held on 31/12, counting the balance it opened with.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Statement {
+    pub movements: Vec<Movement>,
+    /// Optional hand-typed total. When present it must reconcile with
+    /// [`Statement::computed_total`].
+    pub total: Option<Total>,
+}
+
+impl Statement {
+    /// The Total row: the hand-typed or ledger-derived total, or else the computed one.
+    pub fn total(&self, opening: Option<&Total>) -> Total {
+        self.total.unwrap_or_else(|| self.computed_total(opening))
+    }
+
+    /// Derives the Total row from `opening` and the movements at weighted average cost:
+    /// purchases add their cost, sales and withdrawals take out the average cost of what
+    /// leaves, and the price is the average cost of one unit held.
+    pub fn computed_total(&self, opening: Option<&Total>) -> Total {
+        let mut basis = AverageCost(opening.copied().map(Holding::from).unwrap_or_default());
+        for movement in &self.movements {
+            match movement.kind {
+                MovementKind::Bought | MovementKind::Deposited => {
+                    basis.acquire(movement.quantity, movement.cost.unwrap_or_default());
+                }
+                MovementKind::Sold | MovementKind::Withdrawn => {
+                    basis.dispose(movement.quantity);
+                }
+                MovementKind::Fee => {
+                    basis.fee(movement.quantity, movement.cost.unwrap_or_default());
+                }
+            }
+        }
+        basis.holding().total()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Movement {
+    pub kind: MovementKind,
+    /// Unsigned amount of the asset; the sign comes from `kind`.
+    pub quantity: Quantity,
+    /// Unsigned BRL cost the movement adds to or takes out of the position. Typed in only for
+    /// purchases; statements derived from a ledger also carry it on deposits, fees paid in BRL,
+    /// and the average or FIFO cost of what is sold or withdrawn.
+    pub cost: Option<Money>,
+    /// BRL unit price of `cost`, or of the purchase when typed in.
+    pub price: Option<Money>,
+}
+
+impl Movement {
+    /// The cost, negative when it leaves the position with a sale or withdrawal.
+    pub fn signed_cost(&self) -> Option<Money> {
+        let cost = self.cost?;
+        Some(match self.kind {
+            MovementKind::Sold | MovementKind::Withdrawn => Money::from(-cost.amount()),
+            _ => cost,
+        })
+    }
+
+    pub fn signed_quantity(&self) -> Quantity {
+        if self.kind.is_outflow() {
+            -self.quantity
+        } else {
+            self.quantity
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementKind {
+    Bought,
+    Sold,
+    Deposited,
+    Withdrawn,
+    /// Fee charged in the asset itself, or in BRL with a `cost` in a ledger.
+    Fee,
+}
+
+impl MovementKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MovementKind::Bought => "Comprado",
+            MovementKind::Sold => "Vendido",
+            MovementKind::Deposited => "Depositado",
+            MovementKind::Withdrawn => "Sacado",
+            MovementKind::Fee => "Taxa",
+        }
+    }
+
+    /// Whether the movement takes the asset out of the position.
+    pub fn is_outflow(&self) -> bool {
+        matches!(
+            self,
+            MovementKind::Sold | MovementKind::Withdrawn | MovementKind::Fee
+        )
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Total {
+    pub quantity: Quantity,
+    pub cost: Money,
+    /// Absent when nothing was bought.
+    pub price: Option<Money>,
+}
+
+/// One dated entry of an [`AssetPosition::ledger`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub date: NaiveDate,
+    pub kind: MovementKind,
+    /// Unsigned amount of the asset; the sign comes from `kind`.
+    pub quantity: Quantity,
+    /// BRL paid: required for purchases, and optional for deposits, as the acquisition cost at
+    /// the previous custodian, and for fees paid in BRL.
+    pub cost: Option<Money>,
+}
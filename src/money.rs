@@ -0,0 +1,149 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Div, Mul};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+
+/// An exact amount of Brazilian reais.
+///
+/// Displays in pt-BR notation with 2 decimal places, e.g. `R$ 1.234.567,89` or
+/// `-R$ 0,50`. A different number of places can be requested through the formatter precision:
+/// `format!("{:.4}", money)`. Rounding is half away from zero, as usual for BRL amounts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct Money(Decimal);
+
+impl Money {
+    pub const ZERO: Money = Money(Decimal::ZERO);
+
+    pub fn amount(&self) -> Decimal {
+        self.0
+    }
+
+    /// Rounds to `decimals` places half away from zero, the way the amount is displayed.
+    pub fn round(&self, decimals: u32) -> Money {
+        Money(
+            self.0
+                .round_dp_with_strategy(decimals, RoundingStrategy::MidpointAwayFromZero),
+        )
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = f.precision().unwrap_or(2);
+        let rounded = self.round(decimals as u32).0;
+        let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        let digits = format!("{:.*}", decimals, rounded.abs());
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+        write!(f, "{}R$ {}", sign, group_thousands(integer))?;
+        if !fraction.is_empty() {
+            write!(f, ",{}", fraction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inserts pt-BR thousands separators into a string of ASCII digits.
+pub fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+impl From<Decimal> for Money {
+    fn from(amount: Decimal) -> Self {
+        Money(amount)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Mul<Decimal> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: Decimal) -> Money {
+        Money(self.0 * factor)
+    }
+}
+
+impl Div<Decimal> for Money {
+    type Output = Money;
+
+    fn div(self, divisor: Decimal) -> Money {
+        Money(self.0 / divisor)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(amount: &str) -> Money {
+        Money(amount.parse().unwrap())
+    }
+
+    #[test]
+    fn formats_with_thousands_separators() {
+        assert_eq!(money("0").to_string(), "R$ 0,00");
+        assert_eq!(money("999.99").to_string(), "R$ 999,99");
+        assert_eq!(money("1000").to_string(), "R$ 1.000,00");
+        assert_eq!(money("1234567.89").to_string(), "R$ 1.234.567,89");
+        assert_eq!(money("100000000").to_string(), "R$ 100.000.000,00");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(money("-0.5").to_string(), "-R$ 0,50");
+        assert_eq!(money("-1234.5").to_string(), "-R$ 1.234,50");
+        // Rounding to zero doesn't leave a sign behind.
+        assert_eq!(money("-0.001").to_string(), "R$ 0,00");
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(money("10.125").to_string(), "R$ 10,13");
+        assert_eq!(money("10.135").to_string(), "R$ 10,14");
+        assert_eq!(money("-10.125").to_string(), "-R$ 10,13");
+        assert_eq!(money("10.124").to_string(), "R$ 10,12");
+        assert_eq!(money("10.125").round(2), money("10.13"));
+    }
+
+    #[test]
+    fn formats_at_the_requested_precision() {
+        assert_eq!(format!("{:.4}", money("0.0535")), "R$ 0,0535");
+        assert_eq!(format!("{:.0}", money("205000.5")), "R$ 205.001");
+    }
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(group_thousands("1"), "1");
+        assert_eq!(group_thousands("123"), "123");
+        assert_eq!(group_thousands("1234"), "1.234");
+        assert_eq!(group_thousands("123456"), "123.456");
+        assert_eq!(group_thousands("1234567"), "1.234.567");
+    }
+}
@@ -0,0 +1,246 @@
+//! Page furniture: margins, the running header identifying the customer on every page but the
+//! first, and the footer with the page number, the time of issue and the document identifier.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use genpdf::render::Area;
+use genpdf::style::{Color, Style};
+use genpdf::{elements, Alignment, Context, Element, Margins, Mm, PageDecorator, Position};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::masking::Masking;
+use crate::model::Report;
+use crate::tax_year::TaxYear;
+use crate::tenant::Tenant;
+
+/// Brasília time, which the Receita uses and which has had no daylight saving since 2019.
+const BRASILIA_OFFSET: i32 = -3 * 3600;
+
+/// When and under which identifier a document was issued.
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub id: String,
+    pub issued_at: DateTime<FixedOffset>,
+}
+
+impl Issue {
+    /// A fresh random identifier, issued now.
+    pub fn new() -> Self {
+        let brasilia = FixedOffset::east_opt(BRASILIA_OFFSET).expect("offset is within a day");
+        Self {
+            id: Uuid::new_v4().to_string(),
+            issued_at: Utc::now().with_timezone(&brasilia),
+        }
+    }
+}
+
+impl Default for Issue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a header or footer slot shows.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Slot {
+    #[default]
+    Empty,
+    /// "Página 2 de 3".
+    Page,
+    /// "Emitido em 17/10/2026 às 14:32", in Brasília time.
+    IssuedAt,
+    /// "Documento " followed by the [`Issue::id`].
+    DocumentId,
+    /// Name of the beneficiary.
+    Beneficiary,
+    /// "CPF 529.982.247-25", or "CPF ***.982.247-**" under a masking policy that hides it.
+    Cpf,
+    /// "Ano-calendário 2021".
+    TaxYear,
+    /// Fixed text, written as `{ text = "..." }`.
+    Text(String),
+}
+
+/// A row of three slots, from left to right. Slots left out of a profile are empty.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Slots {
+    pub left: Slot,
+    pub center: Slot,
+    pub right: Slot,
+}
+
+impl Slots {
+    /// Running header of every page but the first, so that loose pages can be matched to their
+    /// customer.
+    pub fn header() -> Self {
+        Self {
+            left: Slot::Beneficiary,
+            center: Slot::Cpf,
+            right: Slot::TaxYear,
+        }
+    }
+
+    pub fn footer() -> Self {
+        Self {
+            left: Slot::IssuedAt,
+            center: Slot::Page,
+            right: Slot::DocumentId,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&Slot, Alignment)> {
+        [
+            (&self.left, Alignment::Left),
+            (&self.center, Alignment::Center),
+            (&self.right, Alignment::Right),
+        ]
+        .into_iter()
+    }
+
+    /// Whether a slot shows the total number of pages, which takes a second layout pass.
+    pub(crate) fn needs_page_count(&self) -> bool {
+        self.iter().any(|(slot, _)| matches!(slot, Slot::Page))
+    }
+}
+
+/// Everything a slot can be filled in with.
+struct Fields<'a> {
+    page: usize,
+    pages: Option<usize>,
+    issue: &'a Issue,
+    beneficiary: &'a str,
+    cpf: &'a str,
+    tax_year: TaxYear,
+}
+
+impl Slot {
+    fn text(&self, fields: &Fields) -> String {
+        match self {
+            Slot::Empty => String::new(),
+            Slot::Page => match fields.pages {
+                Some(pages) => format!("Página {} de {}", fields.page, pages),
+                None => format!("Página {}", fields.page),
+            },
+            Slot::IssuedAt => fields
+                .issue
+                .issued_at
+                .format("Emitido em %d/%m/%Y às %H:%M")
+                .to_string(),
+            Slot::DocumentId => format!("Documento {}", fields.issue.id),
+            Slot::Beneficiary => fields.beneficiary.to_string(),
+            Slot::Cpf => format!("CPF {}", fields.cpf),
+            Slot::TaxYear => format!("Ano-calendário {}", fields.tax_year),
+            Slot::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Applies the page margins, prints the running header from the second page on and the footer
+/// at the bottom of every page. Counts the pages it decorates into `rendered`, so that a first
+/// pass can tell the second one the total.
+pub(crate) struct Decorator {
+    page: usize,
+    pages: Option<usize>,
+    rendered: Rc<Cell<usize>>,
+    margins: Margins,
+    header: Slots,
+    footer: Slots,
+    issue: Issue,
+    beneficiary: String,
+    /// The CPF as the render's [`Masking`] policy prints it.
+    cpf: String,
+    tax_year: TaxYear,
+    style: Style,
+    gap: Mm,
+}
+
+impl Decorator {
+    pub(crate) fn new(
+        report: &Report,
+        tenant: &Tenant,
+        masking: &Masking,
+        issue: &Issue,
+        pages: Option<usize>,
+        rendered: Rc<Cell<usize>>,
+    ) -> Self {
+        let theme = &tenant.theme;
+        Self {
+            page: 0,
+            pages,
+            rendered,
+            margins: Margins::from(Mm::from(theme.spacing.page_margins)),
+            header: tenant.header.clone(),
+            footer: tenant.footer.clone(),
+            issue: issue.clone(),
+            beneficiary: report.beneficiary.name.clone(),
+            cpf: masking.cpf(&report.beneficiary.cpf),
+            tax_year: report.tax_year,
+            style: Style::new()
+                .with_font_size(theme.typography.small)
+                .with_color(Color::from(theme.palette.muted)),
+            gap: Mm::from(theme.spacing.block_padding),
+        }
+    }
+
+    /// Prints `slots` in one line at the top of `area`.
+    fn print(
+        &self,
+        slots: &Slots,
+        context: &Context,
+        area: Area<'_>,
+        style: Style,
+    ) -> Result<(), genpdf::error::Error> {
+        let fields = Fields {
+            page: self.page,
+            pages: self.pages,
+            issue: &self.issue,
+            beneficiary: &self.beneficiary,
+            cpf: &self.cpf,
+            tax_year: self.tax_year,
+        };
+        for (area, (slot, alignment)) in area
+            .split_horizontally(&[2, 1, 2])
+            .into_iter()
+            .zip(slots.iter())
+        {
+            elements::Paragraph::new(slot.text(&fields))
+                .aligned(alignment)
+                .render(context, area, style)?;
+        }
+        Ok(())
+    }
+}
+
+impl PageDecorator for Decorator {
+    fn decorate_page<'a>(
+        &mut self,
+        context: &Context,
+        mut area: Area<'a>,
+        style: Style,
+    ) -> Result<Area<'a>, genpdf::error::Error> {
+        self.page += 1;
+        self.rendered.set(self.page);
+        area.add_margins(self.margins);
+
+        let style = style.and(self.style);
+        let height = style.line_height(&context.font_cache);
+
+        if self.page > 1 {
+            self.print(&self.header, context, area.clone(), style)?;
+            area.add_offset(Position::new(0, height + self.gap));
+        }
+
+        let content_height = area.size().height - height - self.gap;
+        let mut footer = area.clone();
+        footer.add_offset(Position::new(0, content_height + self.gap));
+        self.print(&self.footer, context, footer, style)?;
+
+        area.set_height(content_height);
+        Ok(area)
+    }
+}
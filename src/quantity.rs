@@ -0,0 +1,151 @@
+use std::iter::Sum;
+use std::ops::{Add, Neg};
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+
+use crate::asset::AssetDescriptor;
+use crate::money::group_thousands;
+
+/// An exact amount of a crypto asset.
+///
+/// The amount itself carries no precision; it is applied by [`Quantity::round`] and
+/// [`Quantity::format`] from the asset's [`AssetDescriptor::decimals`] (8 for BTC, 18 for
+/// ERC-20 tokens such as PAXG). Rounding is always towards zero, so a balance is never shown as
+/// larger than what is actually held.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct Quantity(Decimal);
+
+impl Quantity {
+    pub const ZERO: Quantity = Quantity(Decimal::ZERO);
+
+    pub fn amount(&self) -> Decimal {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_sign_negative(&self) -> bool {
+        self.0.is_sign_negative()
+    }
+
+    /// Number of decimal places needed to represent the amount exactly.
+    pub fn decimals(&self) -> u32 {
+        self.0.normalize().scale()
+    }
+
+    /// Truncates the amount to the precision of `asset`.
+    pub fn round(&self, asset: &AssetDescriptor) -> Quantity {
+        Quantity(
+            self.0
+                .round_dp_with_strategy(asset.decimals as u32, RoundingStrategy::ToZero),
+        )
+    }
+
+    /// Formats the amount in pt-BR notation followed by the ticker, e.g. `1.234,00012345 BTC`.
+    /// Trailing zeros are dropped.
+    pub fn format(&self, asset: &AssetDescriptor) -> String {
+        let rounded = self.round(asset).0.normalize();
+        let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        let digits = rounded.abs().to_string();
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+        let mut formatted = format!("{}{}", sign, group_thousands(integer));
+        if !fraction.is_empty() {
+            formatted.push(',');
+            formatted.push_str(fraction);
+        }
+        format!("{} {}", formatted, asset.ticker)
+    }
+}
+
+impl From<Decimal> for Quantity {
+    fn from(amount: Decimal) -> Self {
+        Quantity(amount)
+    }
+}
+
+impl Add for Quantity {
+    type Output = Quantity;
+
+    fn add(self, other: Quantity) -> Quantity {
+        Quantity(self.0 + other.0)
+    }
+}
+
+impl Neg for Quantity {
+    type Output = Quantity;
+
+    fn neg(self) -> Quantity {
+        Quantity(-self.0)
+    }
+}
+
+impl Sum for Quantity {
+    fn sum<I: Iterator<Item = Quantity>>(iter: I) -> Quantity {
+        iter.fold(Quantity::ZERO, Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(amount: &str) -> Quantity {
+        Quantity(amount.parse().unwrap())
+    }
+
+    fn format(amount: &str, ticker: &str) -> String {
+        quantity(amount).format(&AssetDescriptor::builtin(ticker).unwrap())
+    }
+
+    #[test]
+    fn formats_with_thousands_separators() {
+        assert_eq!(format("0", "BTC"), "0 BTC");
+        assert_eq!(format("0.3", "BTC"), "0,3 BTC");
+        assert_eq!(format("1234.00012345", "BTC"), "1.234,00012345 BTC");
+        assert_eq!(format("1234567", "USDC"), "1.234.567 USDC");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(format("-0.2", "BTC"), "-0,2 BTC");
+        assert_eq!(format("-1500", "ETH"), "-1.500 ETH");
+        // Truncating to zero doesn't leave a sign behind.
+        assert_eq!(format("-0.000000001", "BTC"), "0 BTC");
+    }
+
+    #[test]
+    fn truncates_to_the_asset_precision() {
+        assert_eq!(format("0.123456789", "BTC"), "0,12345678 BTC");
+        assert_eq!(format("-0.123456789", "BTC"), "-0,12345678 BTC");
+        assert_eq!(format("1.0000009", "USDC"), "1 USDC");
+        assert_eq!(
+            format("0.123456789012345678", "PAXG"),
+            "0,123456789012345678 PAXG"
+        );
+    }
+
+    #[test]
+    fn counts_decimals_without_trailing_zeros() {
+        assert_eq!(quantity("0.50").decimals(), 1);
+        assert_eq!(quantity("100").decimals(), 0);
+        assert_eq!(quantity("0.00000001").decimals(), 8);
+    }
+
+    #[test]
+    fn adds_and_negates() {
+        let sum: Quantity = [quantity("0.5"), -quantity("0.2"), quantity("0.1")]
+            .into_iter()
+            .sum();
+        assert_eq!(sum, quantity("0.4"));
+        assert!((quantity("0.1") + -quantity("0.1")).is_zero());
+    }
+}
@@ -0,0 +1,242 @@
+//! Codes under which the Receita Federal asks for each kind of crypto asset in the Bens e
+//! Direitos section of the DIRPF. They changed with the 2023 declaration, which covers the
+//! ano-calendário 2022: crypto moved to Grupo 08 and got one code per kind of asset. Also writes
+//! the Discriminação customers paste alongside the code.
+
+use serde::Deserialize;
+
+use crate::asset::AssetDescriptor;
+use crate::model::{AssetPosition, Entity, Total};
+use crate::money::Money;
+use crate::tax_id::Cnpj;
+use crate::tax_year::{Period, TaxYear};
+
+/// First ano-calendário declared under Grupo 08.
+const GRUPO_08_SINCE: u16 = 2022;
+
+/// Localização of assets held with a Brazilian custodian, as the IRPF program lists it.
+pub const BRASIL: &str = "105 - Brasil";
+
+/// Kind of crypto asset, which decides its Bens e Direitos code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Bitcoin,
+    /// Other crypto currencies, such as ETH or SOL.
+    Altcoin,
+    /// Tokens pegged to a currency or commodity, such as USDC or PAXG.
+    Stablecoin,
+    Nft,
+    Other,
+}
+
+/// A Bens e Direitos code, e.g. Grupo 08, Código 01.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BensCode {
+    /// `None` for the codes in use before the groups were introduced.
+    pub group: Option<u8>,
+    pub code: u8,
+    pub label: &'static str,
+}
+
+impl BensCode {
+    /// "08 - Criptoativos", or `None` before the groups were introduced.
+    pub fn group_label(&self) -> Option<String> {
+        self.group
+            .map(|group| format!("{:02} - Criptoativos", group))
+    }
+
+    /// "01 - Criptoativo Bitcoin".
+    pub fn code_label(&self) -> String {
+        format!("{:02} - {}", self.code, self.label)
+    }
+
+    /// The code `class` is declared under for `tax_year`.
+    pub fn new(class: AssetClass, tax_year: TaxYear) -> Self {
+        if tax_year.year() >= GRUPO_08_SINCE {
+            let (code, label) = match class {
+                AssetClass::Bitcoin => (1, "Criptoativo Bitcoin"),
+                AssetClass::Altcoin => (2, "Outros criptoativos, conhecidos como altcoins"),
+                AssetClass::Stablecoin => (3, "Criptoativos conhecidos como stablecoins"),
+                AssetClass::Nft => (10, "Criptoativos conhecidos como NFTs"),
+                AssetClass::Other => (99, "Outros criptoativos"),
+            };
+            BensCode {
+                group: Some(8),
+                code,
+                label,
+            }
+        } else {
+            let (code, label) = match class {
+                AssetClass::Bitcoin => (81, "Criptoativo Bitcoin"),
+                AssetClass::Altcoin => (82, "Outros criptoativos do tipo moeda digital"),
+                AssetClass::Stablecoin | AssetClass::Nft | AssetClass::Other => {
+                    (89, "Outros criptoativos")
+                }
+            };
+            BensCode {
+                group: None,
+                code,
+                label,
+            }
+        }
+    }
+}
+
+/// The Bens e Direitos ficha of one asset, field by field as the IRPF program asks for it.
+pub struct Ficha {
+    pub code: BensCode,
+    pub localizacao: &'static str,
+    /// CNPJ of the custodian.
+    pub cnpj: Cnpj,
+    pub discriminacao: String,
+    /// Acquisition cost of what was held on 31/12 of the previous year.
+    pub situacao_anterior: Money,
+    /// Acquisition cost of what was held on 31/12 of the ano-calendário, counting the balance
+    /// carried over from the previous year.
+    pub situacao_atual: Money,
+}
+
+impl Ficha {
+    pub fn new(
+        asset: &AssetDescriptor,
+        position: &AssetPosition,
+        tax_year: TaxYear,
+        custodian: &Entity,
+    ) -> Self {
+        let cost = |period| {
+            position
+                .total(period)
+                .map_or(Money::ZERO, |total| total.cost)
+        };
+        let current = position
+            .current
+            .total(position.opening(Period::Current).as_ref());
+        Ficha {
+            code: asset.bens_code(tax_year),
+            localizacao: BRASIL,
+            cnpj: custodian.cnpj,
+            discriminacao: discriminacao(asset, &current, custodian),
+            situacao_anterior: cost(Period::Previous),
+            situacao_atual: cost(Period::Current),
+        }
+    }
+}
+
+/// Longest Discriminação the IRPF program accepts.
+pub const DISCRIMINACAO_LIMIT: usize = 512;
+
+/// Ready-to-paste Discriminação of `asset`, held with `custodian`, whose position is `total`,
+/// cut to [`DISCRIMINACAO_LIMIT`] characters.
+pub fn discriminacao(asset: &AssetDescriptor, total: &Total, custodian: &Entity) -> String {
+    let text = format!(
+        "Saldo de {} ({}) mantido em custódia na {}, CNPJ {}. Custo total de aquisição: {}.",
+        total.quantity.format(asset),
+        asset.name,
+        custodian.name,
+        custodian.cnpj,
+        total.cost,
+    );
+    if text.chars().count() <= DISCRIMINACAO_LIMIT {
+        return text;
+    }
+    let mut cut: String = text.chars().take(DISCRIMINACAO_LIMIT - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    fn code(ticker: &str, year: u16) -> BensCode {
+        AssetDescriptor::builtin(ticker)
+            .unwrap()
+            .bens_code(TaxYear::new(year))
+    }
+
+    #[test]
+    fn uses_the_legacy_codes_before_grupo_08() {
+        for (ticker, expected) in [("BTC", 81), ("ETH", 82), ("USDC", 89)] {
+            let code = code(ticker, 2021);
+            assert_eq!(code.group, None, "{}", ticker);
+            assert_eq!(code.code, expected, "{}", ticker);
+        }
+        assert_eq!(code("BTC", 2021).group_label(), None);
+    }
+
+    #[test]
+    fn uses_grupo_08_from_2022() {
+        let classes = [
+            (AssetClass::Bitcoin, 1),
+            (AssetClass::Altcoin, 2),
+            (AssetClass::Stablecoin, 3),
+            (AssetClass::Nft, 10),
+            (AssetClass::Other, 99),
+        ];
+        for (class, expected) in classes {
+            let code = BensCode::new(class, TaxYear::new(2022));
+            assert_eq!(code.group, Some(8), "{:?}", class);
+            assert_eq!(code.code, expected, "{:?}", class);
+        }
+        assert_eq!(code("ETH", 2022).code, 2);
+        assert_eq!(code("USDC", 2023).code, 3);
+        assert_eq!(
+            code("BTC", 2022).group_label().as_deref(),
+            Some("08 - Criptoativos")
+        );
+        assert_eq!(code("BTC", 2022).code_label(), "01 - Criptoativo Bitcoin");
+    }
+
+    #[test]
+    fn headings_follow_the_era() {
+        let btc = AssetDescriptor::builtin("BTC").unwrap();
+        assert_eq!(
+            btc.heading(TaxYear::new(2021)),
+            "81 - Criptoativo Bitcoin (Bitcoin - BTC)"
+        );
+        assert_eq!(
+            btc.heading(TaxYear::new(2022)),
+            "Grupo 08 - Código 01 - Criptoativo Bitcoin (Bitcoin - BTC)"
+        );
+    }
+
+    fn custodian(name: &str) -> Entity {
+        Entity {
+            name: name.to_string(),
+            cnpj: "37.008.710/0001-78".parse().unwrap(),
+        }
+    }
+
+    fn total() -> Total {
+        Total {
+            quantity: Quantity::from(Decimal::new(3, 1)),
+            cost: Money::from(Decimal::new(4612500, 2)),
+            price: None,
+        }
+    }
+
+    #[test]
+    fn leaves_a_short_discriminacao_whole() {
+        let btc = AssetDescriptor::builtin("BTC").unwrap();
+        let text = discriminacao(&btc, &total(), &custodian("Bipa"));
+        assert_eq!(
+            text,
+            "Saldo de 0,3 BTC (Bitcoin) mantido em custódia na Bipa, CNPJ 37.008.710/0001-78. \
+             Custo total de aquisição: R$ 46.125,00."
+        );
+    }
+
+    #[test]
+    fn cuts_a_long_discriminacao_to_the_limit() {
+        let btc = AssetDescriptor::builtin("BTC").unwrap();
+        let name = "Intermediação de Ativos Digitais ".repeat(20);
+        let text = discriminacao(&btc, &total(), &custodian(&name));
+        assert_eq!(text.chars().count(), DISCRIMINACAO_LIMIT);
+        assert!(text.ends_with('…'));
+        assert!(text.starts_with("Saldo de 0,3 BTC (Bitcoin) mantido em custódia na"));
+    }
+}
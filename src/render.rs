@@ -0,0 +1,503 @@
+use std::cell::Cell;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use genpdf::elements::{Image, PageBreak};
+use genpdf::fonts::{FontData, FontFamily};
+use genpdf::style::{Color, Style};
+use genpdf::{elements, style, Alignment, Document, Element, Scale};
+
+use crate::assets;
+use crate::cost::CostMethod;
+use crate::error::Error;
+use crate::input::InputError;
+use crate::layout::{
+    add_empty_row, asset_section, build_table, ficha_section, linear_layout, row_table, RowData,
+};
+use crate::masking::Masking;
+use crate::model::{Entity, Report};
+use crate::page::{Decorator, Issue};
+use crate::receita::{discriminacao, Ficha};
+use crate::tax_year::Period;
+use crate::tenant::Tenant;
+use crate::theme::Spacing;
+
+/// Overrides for the assets and branding the report is rendered with. The default renders with
+/// the embedded fonts under Bipa's own [`Tenant`] profile.
+#[derive(Default)]
+pub struct RenderOptions {
+    /// Directory holding the Roboto font family. `None` picks the default described in
+    /// [`crate::assets`].
+    pub fonts_dir: Option<PathBuf>,
+    /// Brand the report is issued under.
+    pub tenant: Tenant,
+    /// Identifier and time of issue printed in the footer. `None` issues a fresh identifier at
+    /// the current time on every render.
+    pub issue: Option<Issue>,
+    /// Hides the beneficiary's personal data as the tenant's [`Masking`] policy says, for copies
+    /// sent by e-mail or shown in support tools.
+    pub masked: bool,
+}
+
+impl RenderOptions {
+    /// The masking policy in effect for this render.
+    pub fn masking(&self) -> Masking {
+        if self.masked {
+            self.tenant.masking.clone()
+        } else {
+            Masking::none()
+        }
+    }
+
+    /// The report's paying source, or the tenant's if the report doesn't name one.
+    pub fn payer<'a>(&'a self, report: &'a Report) -> Result<&'a Entity, Error> {
+        entity("payer", report.payer.as_ref(), self.tenant.payer.as_ref())
+    }
+
+    /// The report's crypto custodian, or the tenant's if the report doesn't name one.
+    pub fn custodian<'a>(&'a self, report: &'a Report) -> Result<&'a Entity, Error> {
+        entity(
+            "custodian",
+            report.custodian.as_ref(),
+            self.tenant.custodian.as_ref(),
+        )
+    }
+
+    /// Checks what rendering `report` with these options needs beyond a valid input, without
+    /// rendering it.
+    pub fn check(&self, report: &Report) -> Result<(), Error> {
+        self.payer(report)?;
+        self.custodian(report)?;
+        Ok(())
+    }
+}
+
+/// The report's own entity, or the tenant's if the report doesn't name one.
+fn entity<'a>(
+    field: &str,
+    report: Option<&'a Entity>,
+    tenant: Option<&'a Entity>,
+) -> Result<&'a Entity, Error> {
+    report.or(tenant).ok_or_else(|| {
+        Error::Input(InputError::Invalid {
+            field: field.to_string(),
+            reason: "missing, and the tenant doesn't provide one".to_string(),
+        })
+    })
+}
+
+/// Renders any number of reports with the same options, loading the fonts and logo only once.
+/// It is `Sync`, so one renderer can be shared by a pool of worker threads.
+pub struct Renderer {
+    options: RenderOptions,
+    fonts: FontFamily<FontData>,
+    logo: Image,
+}
+
+impl Renderer {
+    pub fn new(options: RenderOptions) -> Result<Self, Error> {
+        Ok(Self {
+            fonts: assets::load_fonts(options.fonts_dir.as_deref())?,
+            logo: assets::load_logo(options.tenant.logo.as_deref())?,
+            options,
+        })
+    }
+
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    /// See [`build_document`].
+    pub fn build_document(&self, report: &Report) -> Result<Document, Error> {
+        layout(report, &self.options, &self.fonts, &self.logo).map(|(doc, _)| doc)
+    }
+
+    /// See [`render`]. Returns the number of pages written.
+    pub fn render(&self, report: &Report, writer: impl io::Write) -> Result<usize, Error> {
+        let (doc, pages) = layout(report, &self.options, &self.fonts, &self.logo)?;
+        doc.render(writer).map_err(Error::Render)?;
+        Ok(pages.get())
+    }
+
+    /// See [`render_to_vec`].
+    pub fn render_to_vec(&self, report: &Report) -> Result<Vec<u8>, Error> {
+        let mut pdf = Vec::new();
+        self.render(report, &mut pdf)?;
+        Ok(pdf)
+    }
+
+    /// See [`render_to_file`].
+    pub fn render_to_file(&self, report: &Report, output: &Path) -> Result<(), Error> {
+        self.build_document(report)?
+            .render_to_file(output)
+            .map_err(Error::Render)
+    }
+}
+
+/// Builds the Informe de Rendimentos for `report`, ready to be rendered.
+pub fn build_document(report: &Report, options: &RenderOptions) -> Result<Document, Error> {
+    let fonts = assets::load_fonts(options.fonts_dir.as_deref())?;
+    let logo = assets::load_logo(options.tenant.logo.as_deref())?;
+    layout(report, options, &fonts, &logo).map(|(doc, _)| doc)
+}
+
+/// Lays out the document, along with the number of pages it had once it is rendered.
+fn layout(
+    report: &Report,
+    options: &RenderOptions,
+    fonts: &FontFamily<FontData>,
+    logo: &Image,
+) -> Result<(Document, Rc<Cell<usize>>), Error> {
+    let issue = options.issue.clone().unwrap_or_default();
+    let masking = options.masking();
+    let decorator = |pages, rendered| {
+        Decorator::new(report, &options.tenant, &masking, &issue, pages, rendered)
+    };
+
+    // genpdf only knows how many pages there are once the document is laid out, so showing the
+    // total takes a first pass that is thrown away.
+    let tenant = &options.tenant;
+    let pages = if tenant.header.needs_page_count() || tenant.footer.needs_page_count() {
+        let rendered = Rc::new(Cell::new(0));
+        let decorator = decorator(None, Rc::clone(&rendered));
+        document(report, options, fonts.clone(), logo.clone(), decorator)?
+            .render(io::sink())
+            .map_err(Error::Render)?;
+        Some(rendered.get())
+    } else {
+        None
+    };
+
+    let rendered = Rc::new(Cell::new(0));
+    let decorator = decorator(pages, Rc::clone(&rendered));
+    let doc = document(report, options, fonts.clone(), logo.clone(), decorator)?;
+    Ok((doc, rendered))
+}
+
+fn document(
+    report: &Report,
+    options: &RenderOptions,
+    fonts: FontFamily<FontData>,
+    mut image: Image,
+    decorator: Decorator,
+) -> Result<Document, Error> {
+    let tenant = &options.tenant;
+    let theme = &tenant.theme;
+    let palette = &theme.palette;
+    let typography = &theme.typography;
+    let spacing = &theme.spacing;
+    let masking = options.masking();
+    let beneficiary = &report.beneficiary;
+
+    let mut doc = Document::new(fonts);
+    doc.set_title(format!(
+        "{} {} - {} (CPF {})",
+        tenant.title,
+        report.tax_year,
+        beneficiary.name,
+        masking.cpf(&beneficiary.cpf)
+    ));
+    doc.set_minimal_conformance();
+    doc.set_line_spacing(spacing.line_spacing);
+    doc.set_page_decorator(decorator);
+
+    let mut table = elements::TableLayout::new(vec![1, 1]);
+    image.set_scale(Scale::new(0.3, 0.3));
+    image.set_alignment(Alignment::Left);
+
+    let brand = Color::from(palette.brand);
+    let muted = Color::from(palette.muted);
+    let text = Color::from(palette.text);
+    let rule = Color::from(palette.rule);
+
+    table
+        .row()
+        .element(image)
+        .element(
+            elements::Paragraph::new(tenant.title.as_str())
+                .aligned(Alignment::Right)
+                .styled(
+                    style::Style::new()
+                        .bold()
+                        .with_font_size(typography.heading),
+                )
+                .styled(brand),
+        )
+        .push()
+        .map_err(Error::InvalidTableRow)?;
+
+    doc.push(table);
+
+    doc.push(elements::Break::new(1.0));
+
+    doc.push(
+        elements::Paragraph::new("Imposto de Renda - Pessoa Física")
+            .aligned(Alignment::Left)
+            .styled(style::Style::new().bold().with_font_size(typography.title)),
+    );
+
+    let payer = options.payer(report)?;
+    let layout = linear_layout(
+        theme,
+        "Idenficação da Fonte Pagadora",
+        &payer.name,
+        elements::Paragraph::default()
+            .styled_string("CNPJ:", muted)
+            .string(format!(" {}", payer.cnpj))
+            .styled(style::Style::new().with_font_size(typography.body))
+            .styled(text),
+    );
+
+    let layout1 = linear_layout(
+        theme,
+        "Pessoa Física Beneficiária dos Rendimentos",
+        &beneficiary.name,
+        elements::Paragraph::default()
+            .styled_string("CPF:", muted)
+            .string(format!(" {}", masking.cpf(&beneficiary.cpf)))
+            .styled_string("    Agência:", muted)
+            .string(format!(" {}", masking.agency(&beneficiary.agency)))
+            .styled_string("    Conta:", muted)
+            .string(format!(" {}", masking.account(&beneficiary.account)))
+            .styled(style::Style::new().with_font_size(typography.body))
+            .styled(text),
+    );
+
+    let rows = vec![layout, layout1];
+    let mut table = build_table(rows, Spacing::around(spacing.section_padding))?;
+    add_empty_row(&mut table)?;
+
+    let table = table.styled(rule);
+    doc.push(elements::Break::new(1.0));
+    doc.push(table);
+    doc.push(elements::Break::new(1));
+
+    let fixed_income = &report.fixed_income;
+    let data1 = vec![
+        RowData::new(
+            "Rendimentos Sujeitos a Tributação Exclusiva",
+            Style::new()
+                .with_color(text)
+                .with_font_size(typography.subheading)
+                .bold(),
+            Alignment::Left,
+        ),
+        RowData::new(
+            "Valores em R$",
+            Style::new()
+                .with_color(rule)
+                .with_font_size(typography.table),
+            Alignment::Right,
+        ),
+    ];
+    let row1 = row_table(theme, data1)?;
+
+    let leading_row_style = Style::new()
+        .with_color(muted)
+        .with_font_size(typography.table);
+    let trailing_row_style = Style::new()
+        .with_color(text)
+        .with_font_size(typography.table)
+        .bold();
+    let data2 = vec![
+        RowData::new(
+            &format!(
+                "Saldo em {}",
+                report.tax_year.closing_date(Period::Previous)
+            ),
+            leading_row_style,
+            Alignment::Left,
+        ),
+        RowData::new(
+            &fixed_income.opening_balance.to_string(),
+            trailing_row_style,
+            Alignment::Right,
+        ),
+    ];
+    let row2 = row_table(theme, data2)?;
+
+    let data3 = vec![
+        RowData::new(
+            &format!("Saldo em {}", report.tax_year.closing_date(Period::Current)),
+            leading_row_style,
+            Alignment::Left,
+        ),
+        RowData::new(
+            &fixed_income.closing_balance.to_string(),
+            trailing_row_style,
+            Alignment::Right,
+        ),
+    ];
+    let row3 = row_table(theme, data3)?;
+
+    let leading_row_style = Style::new()
+        .with_color(brand)
+        .with_font_size(typography.small);
+    let trailing_row_style = Style::new()
+        .with_color(brand)
+        .with_font_size(typography.table)
+        .bold();
+    let data4 = vec![
+        RowData::new("Rendimentos Líquidos", leading_row_style, Alignment::Left),
+        RowData::new(
+            &fixed_income.net_income.to_string(),
+            trailing_row_style,
+            Alignment::Right,
+        ),
+    ];
+    let row4 = row_table(theme, data4)?;
+
+    let rows = vec![row1, row2, row3, row4];
+    let table = build_table(rows, Spacing::around(spacing.row_padding))?;
+    let table = table.styled(rule);
+
+    doc.push(table);
+
+    doc.push(elements::PageBreak::new());
+
+    let custodian = options.custodian(report)?;
+    let layout = linear_layout(
+        theme,
+        "Idenficação da Fonte Compradora e Custodiante de Criptoativos",
+        &custodian.name,
+        elements::Paragraph::default()
+            .styled_string("CNPJ:", muted)
+            .string(format!(" {}", custodian.cnpj))
+            .styled(style::Style::new().with_font_size(typography.body))
+            .styled(text),
+    );
+
+    let rows = vec![layout];
+    let mut table = build_table(rows, Spacing::around(spacing.section_padding))?;
+    add_empty_row(&mut table)?;
+
+    let table = table.styled(rule);
+    doc.push(table);
+
+    let asset = |i: usize, ticker: &str| {
+        report.asset(ticker).ok_or_else(|| {
+            Error::Input(InputError::Invalid {
+                field: format!("positions[{}].asset", i),
+                reason: format!("unknown asset `{}`", ticker),
+            })
+        })
+    };
+
+    let mut first_page = true;
+    for period in Period::ALL {
+        let statements: Vec<_> = report
+            .positions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, position)| Some((i, position, position.statement(period)?)))
+            .collect();
+        if statements.is_empty() {
+            continue;
+        }
+
+        if !first_page {
+            doc.push(PageBreak::new());
+        }
+        first_page = false;
+
+        for (j, (i, position, statement)) in statements.into_iter().enumerate() {
+            let asset = asset(i, &position.asset)?;
+            let title = if j == 0 {
+                Some("Bens e Direitos")
+            } else {
+                None
+            };
+            let opening = position.opening(period);
+            let total = statement.total(opening.as_ref());
+            let discriminacao = discriminacao(&asset, &total, custodian);
+            doc.push(asset_section(
+                theme,
+                title,
+                &asset,
+                report.tax_year,
+                period,
+                statement,
+                opening.as_ref(),
+                &discriminacao,
+            )?);
+        }
+    }
+
+    if !report.positions.is_empty() {
+        doc.push(PageBreak::new());
+    }
+    for (i, position) in report.positions.iter().enumerate() {
+        let asset = asset(i, &position.asset)?;
+        let title = if i == 0 {
+            Some("Ficha de Bens e Direitos")
+        } else {
+            None
+        };
+        let ficha = Ficha::new(&asset, position, report.tax_year, custodian);
+        doc.push(ficha_section(
+            theme,
+            title,
+            &asset,
+            report.tax_year,
+            &ficha,
+        )?);
+    }
+
+    let derived = report
+        .positions
+        .iter()
+        .any(|position| position.ledger.is_some());
+    if derived && report.cost_method != CostMethod::Average {
+        doc.push(elements::Break::new(1));
+        doc.push(
+            elements::Paragraph::new(format!(
+                "Custos de aquisição apurados pelo método {}, para conciliação. Na declaração, \
+                 a Receita Federal exige o custo médio ponderado.",
+                report.cost_method.label()
+            ))
+            .styled(Style::new().with_font_size(typography.small))
+            .styled(muted),
+        );
+    }
+
+    for disclaimer in &tenant.disclaimers {
+        doc.push(elements::Break::new(1));
+        doc.push(
+            elements::Paragraph::new(disclaimer.as_str())
+                .styled(Style::new().with_font_size(typography.small))
+                .styled(muted),
+        );
+    }
+
+    Ok(doc)
+}
+
+/// Renders the PDF for `report` into `writer`, e.g. an HTTP response body or an archive entry.
+pub fn render(
+    report: &Report,
+    options: &RenderOptions,
+    writer: impl io::Write,
+) -> Result<(), Error> {
+    build_document(report, options)?
+        .render(writer)
+        .map_err(Error::Render)
+}
+
+/// Renders the PDF for `report` into memory.
+pub fn render_to_vec(report: &Report, options: &RenderOptions) -> Result<Vec<u8>, Error> {
+    let mut pdf = Vec::new();
+    render(report, options, &mut pdf)?;
+    Ok(pdf)
+}
+
+/// Renders the PDF for `report` into the file at `output`, replacing it if it exists.
+pub fn render_to_file(
+    report: &Report,
+    options: &RenderOptions,
+    output: &Path,
+) -> Result<(), Error> {
+    build_document(report, options)?
+        .render_to_file(output)
+        .map_err(Error::Render)
+}
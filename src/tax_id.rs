@@ -0,0 +1,243 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+/// Failure to read a CPF or CNPJ: it doesn't have the right number of digits, or contains
+/// something other than digits and the usual `.`, `-` and `/` punctuation.
+#[derive(Debug)]
+pub struct TaxIdFormatError {
+    kind: &'static str,
+    expected: usize,
+}
+
+impl fmt::Display for TaxIdFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a {} has {} digits, optionally punctuated with `.`, `-` and `/`",
+            self.kind, self.expected
+        )
+    }
+}
+
+impl std::error::Error for TaxIdFormatError {}
+
+fn parse_digits<const N: usize>(s: &str, kind: &'static str) -> Result<[u8; N], TaxIdFormatError> {
+    let error = || TaxIdFormatError { kind, expected: N };
+    let mut digits = [0; N];
+    let mut len = 0;
+    for c in s.trim().chars() {
+        match c {
+            '0'..='9' if len < N => {
+                digits[len] = c as u8 - b'0';
+                len += 1;
+            }
+            '.' | '-' | '/' => {}
+            _ => return Err(error()),
+        }
+    }
+    if len == N {
+        Ok(digits)
+    } else {
+        Err(error())
+    }
+}
+
+/// Modulo 11 check digit shared by CPF and CNPJ, given the weight of each digit.
+fn check_digit(digits: &[u8], weights: impl Iterator<Item = u32>) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .zip(weights)
+        .map(|(&digit, weight)| digit as u32 * weight)
+        .sum();
+    match sum % 11 {
+        0 | 1 => 0,
+        remainder => (11 - remainder) as u8,
+    }
+}
+
+fn all_equal(digits: &[u8]) -> bool {
+    digits.iter().all(|&digit| digit == digits[0])
+}
+
+fn write_digits(f: &mut fmt::Formatter<'_>, digits: &[u8]) -> fmt::Result {
+    digits.iter().try_for_each(|digit| write!(f, "{}", digit))
+}
+
+/// Cadastro de Pessoas Físicas number. Parsing only checks the shape; use [`Cpf::is_valid`] for
+/// the check digits. Displays as `000.000.000-00`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cpf([u8; 11]);
+
+impl Cpf {
+    /// Checks both check digits. Numbers made of a single repeated digit are rejected too, as
+    /// they pass the arithmetic but are never issued.
+    pub fn is_valid(&self) -> bool {
+        let digits = &self.0;
+        !all_equal(digits)
+            && check_digit(&digits[..9], (2..=10).rev()) == digits[9]
+            && check_digit(&digits[..10], (2..=11).rev()) == digits[10]
+    }
+
+    /// The CPF with the first three and the check digits hidden, e.g. `***.982.247-**`, as the
+    /// Receita prints it on public documents.
+    pub fn masked(&self) -> String {
+        let d = &self.0;
+        let digits = |range: std::ops::Range<usize>| {
+            d[range]
+                .iter()
+                .map(|digit| digit.to_string())
+                .collect::<String>()
+        };
+        format!("***.{}.{}-**", digits(3..6), digits(6..9))
+    }
+}
+
+impl FromStr for Cpf {
+    type Err = TaxIdFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_digits(s, "CPF").map(Cpf)
+    }
+}
+
+impl fmt::Display for Cpf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.0;
+        write_digits(f, &d[0..3])?;
+        f.write_str(".")?;
+        write_digits(f, &d[3..6])?;
+        f.write_str(".")?;
+        write_digits(f, &d[6..9])?;
+        f.write_str("-")?;
+        write_digits(f, &d[9..11])
+    }
+}
+
+impl<'de> Deserialize<'de> for Cpf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Cadastro Nacional da Pessoa Jurídica number. Parsing only checks the shape; use
+/// [`Cnpj::is_valid`] for the check digits. Displays as `00.000.000/0000-00`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cnpj([u8; 14]);
+
+impl Cnpj {
+    pub fn is_valid(&self) -> bool {
+        let digits = &self.0;
+        let weights = |len| (2..=9).cycle().take(len).collect::<Vec<u32>>();
+        !all_equal(digits)
+            && check_digit(&digits[..12], weights(12).into_iter().rev()) == digits[12]
+            && check_digit(&digits[..13], weights(13).into_iter().rev()) == digits[13]
+    }
+}
+
+impl FromStr for Cnpj {
+    type Err = TaxIdFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_digits(s, "CNPJ").map(Cnpj)
+    }
+}
+
+impl fmt::Display for Cnpj {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.0;
+        write_digits(f, &d[0..2])?;
+        f.write_str(".")?;
+        write_digits(f, &d[2..5])?;
+        f.write_str(".")?;
+        write_digits(f, &d[5..8])?;
+        f.write_str("/")?;
+        write_digits(f, &d[8..12])?;
+        f.write_str("-")?;
+        write_digits(f, &d[12..14])
+    }
+}
+
+impl<'de> Deserialize<'de> for Cnpj {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpf(s: &str) -> Cpf {
+        s.parse().unwrap()
+    }
+
+    fn cnpj(s: &str) -> Cnpj {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn accepts_valid_cpfs() {
+        for valid in [
+            "529.982.247-25",
+            "52998224725",
+            "111.444.777-35",
+            "000.000.001-91",
+        ] {
+            assert!(cpf(valid).is_valid(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_cpfs() {
+        for invalid in [
+            "529.982.247-24",
+            "529.982.247-15",
+            "111.444.777-53",
+            "111.111.111-11",
+        ] {
+            assert!(!cpf(invalid).is_valid(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn accepts_valid_cnpjs() {
+        for valid in [
+            "13.140.088/0001-99",
+            "37.008.710/0001-78",
+            "11.222.333/0001-81",
+            "11222333000181",
+        ] {
+            assert!(cnpj(valid).is_valid(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_cnpjs() {
+        for invalid in [
+            "13.140.088/0001-98",
+            "11.222.333/0001-18",
+            "00.000.000/0000-00",
+        ] {
+            assert!(!cnpj(invalid).is_valid(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        for malformed in ["529.982.247-2", "529.982.247-255", "529 982 247 25", "abc"] {
+            assert!(malformed.parse::<Cpf>().is_err(), "{}", malformed);
+        }
+        assert!("11.222.333/0001".parse::<Cnpj>().is_err());
+    }
+
+    #[test]
+    fn formats_and_masks() {
+        assert_eq!(cpf("52998224725").to_string(), "529.982.247-25");
+        assert_eq!(cpf("52998224725").masked(), "***.982.247-**");
+        assert_eq!(cnpj("11222333000181").to_string(), "11.222.333/0001-81");
+    }
+}
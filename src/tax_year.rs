@@ -0,0 +1,46 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+/// The ano-calendário a report covers. Every reference date in the document is derived from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct TaxYear(u16);
+
+impl TaxYear {
+    pub fn new(year: u16) -> Self {
+        Self(year)
+    }
+
+    pub fn year(&self) -> u16 {
+        self.0
+    }
+
+    /// Closing date of `period`, e.g. "31/12/2020" for [`Period::Previous`] of 2021.
+    pub fn closing_date(&self, period: Period) -> String {
+        let year = match period {
+            Period::Current => self.0,
+            Period::Previous => self.0 - 1,
+        };
+        format!("31/12/{}", year)
+    }
+}
+
+impl fmt::Display for TaxYear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The two situations the Receita asks for: 31/12 of the previous year and 31/12 of the
+/// ano-calendário.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Period {
+    Current,
+    Previous,
+}
+
+impl Period {
+    /// Order in which the periods are rendered.
+    pub const ALL: [Period; 2] = [Period::Current, Period::Previous];
+}
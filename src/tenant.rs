@@ -0,0 +1,142 @@
+//! Tenant profiles: the brand a report is issued under. Besides Bipa itself, we issue reports on
+//! behalf of partner brokers, each with their own logo, look, legal entities and fine print. A
+//! profile is usually loaded from a TOML file:
+//!
+//! ```toml
+//! title = "Informe de Rendimentos"
+//! logo = "corretora.png"
+//! disclaimers = ["Documento emitido pela Corretora S.A. por meio da Bipa."]
+//!
+//! [payer]
+//! name = "Corretora S.A."
+//! cnpj = "00.000.000/0001-91"
+//!
+//! [custodian]
+//! name = "Corretora S.A."
+//! cnpj = "00.000.000/0001-91"
+//!
+//! [theme.palette]
+//! brand = [230, 57, 70]
+//!
+//! [footer]
+//! left = { text = "Corretora S.A." }
+//! center = "page"
+//! right = "document_id"
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::input::InputError;
+use crate::masking::Masking;
+use crate::model::Entity;
+use crate::page::Slots;
+use crate::theme::Theme;
+
+const DEFAULT_TITLE: &str = "Informe de Rendimentos Financeiro";
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tenant {
+    /// Document name shown next to the logo.
+    #[serde(default = "default_title")]
+    pub title: String,
+    /// Image shown at the top of the first page. `None` picks the default described in
+    /// [`crate::assets`]. Relative paths in a profile file are resolved against its directory.
+    #[serde(default)]
+    pub logo: Option<PathBuf>,
+    #[serde(default)]
+    pub theme: Theme,
+    /// Paying source used when the report doesn't name one.
+    #[serde(default)]
+    pub payer: Option<Entity>,
+    /// Custodian used when the report doesn't name one.
+    #[serde(default)]
+    pub custodian: Option<Entity>,
+    /// Paragraphs printed in small type at the end of the document.
+    #[serde(default)]
+    pub disclaimers: Vec<String>,
+    /// Running header of every page but the first.
+    #[serde(default = "Slots::header")]
+    pub header: Slots,
+    #[serde(default = "Slots::footer")]
+    pub footer: Slots,
+    /// What masked copies hide, see [`crate::RenderOptions::masked`].
+    #[serde(default)]
+    pub masking: Masking,
+}
+
+fn default_title() -> String {
+    DEFAULT_TITLE.to_string()
+}
+
+impl Tenant {
+    /// Reads a profile from the TOML file at `path`.
+    pub fn load(path: &Path) -> Result<Tenant, InputError> {
+        let toml = fs::read_to_string(path).map_err(|source| InputError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut tenant = Tenant::from_toml(&toml)?;
+        if let (Some(logo), Some(dir)) = (&tenant.logo, path.parent()) {
+            tenant.logo = Some(dir.join(logo));
+        }
+        Ok(tenant)
+    }
+
+    /// Decodes a TOML profile. Errors point at the offending key, e.g. `payer.cnpj`.
+    pub fn from_toml(toml: &str) -> Result<Tenant, InputError> {
+        let invalid = |field: &str, reason: String| InputError::Invalid {
+            field: field.to_string(),
+            reason,
+        };
+        let de =
+            toml::Deserializer::parse(toml).map_err(|err| invalid("<root>", err.to_string()))?;
+        let tenant: Tenant = serde_path_to_error::deserialize(de)
+            .map_err(|err| invalid(&err.path().to_string(), err.into_inner().to_string()))?;
+
+        let entities = [("payer", &tenant.payer), ("custodian", &tenant.custodian)];
+        for (field, entity) in entities {
+            if let Some(entity) = entity.as_ref().filter(|entity| !entity.cnpj.is_valid()) {
+                return Err(invalid(
+                    &format!("{}.cnpj", field),
+                    format!("CNPJ {} has invalid check digits", entity.cnpj),
+                ));
+            }
+        }
+        Ok(tenant)
+    }
+
+    /// Bipa's own profile, with Acesso as the paying source.
+    pub fn bipa() -> Tenant {
+        let entity = |name: &str, cnpj: &str| Entity {
+            name: name.to_string(),
+            cnpj: cnpj.parse().expect("built-in CNPJs are well formed"),
+        };
+        Tenant {
+            title: default_title(),
+            logo: None,
+            theme: Theme::default(),
+            payer: Some(entity(
+                "Acesso Soluções de Pagamento S.A.",
+                "13.140.088/0001-99",
+            )),
+            custodian: Some(entity(
+                "Bipa Intermediação de Ativos Digitais LTDA",
+                "37.008.710/0001-78",
+            )),
+            disclaimers: Vec::new(),
+            header: Slots::header(),
+            footer: Slots::footer(),
+            masking: Masking::default(),
+        }
+    }
+}
+
+impl Default for Tenant {
+    fn default() -> Self {
+        Tenant::bipa()
+    }
+}
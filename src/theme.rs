@@ -0,0 +1,162 @@
+//! Colors, font sizes and spacing of the report, so it can be restyled without touching the
+//! section builders. A [`Theme`] is usually loaded from a TOML file; every key is optional and
+//! falls back to the default Bipa look:
+//!
+//! ```toml
+//! [palette]
+//! brand = [0, 206, 120]
+//!
+//! [typography]
+//! title = 18
+//!
+//! [spacing]
+//! line_spacing = 1.4
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use genpdf::style::Color;
+use genpdf::{Margins, Mm};
+use serde::Deserialize;
+
+use crate::input::InputError;
+
+/// An RGB color, written as `[r, g, b]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Rgb(pub [u8; 3]);
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Color {
+        let [r, g, b] = rgb.0;
+        Color::Rgb(r, g, b)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub palette: Palette,
+    pub typography: Typography,
+    pub spacing: Spacing,
+}
+
+impl Theme {
+    /// Reads a theme from the TOML file at `path`.
+    pub fn load(path: &Path) -> Result<Theme, InputError> {
+        let toml = fs::read_to_string(path).map_err(|source| InputError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Theme::from_toml(&toml)
+    }
+
+    /// Decodes a TOML theme. Errors point at the offending key, e.g. `palette.brand`.
+    pub fn from_toml(toml: &str) -> Result<Theme, InputError> {
+        let invalid = |field: String, reason: String| InputError::Invalid { field, reason };
+        let de = toml::Deserializer::parse(toml)
+            .map_err(|err| invalid("<root>".to_string(), err.to_string()))?;
+        serde_path_to_error::deserialize(de)
+            .map_err(|err| invalid(err.path().to_string(), err.into_inner().to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Palette {
+    /// Titles of the identification blocks and the Rendimentos Líquidos row.
+    pub brand: Rgb,
+    /// Body text.
+    pub text: Rgb,
+    /// Labels such as "CNPJ:" or "Saldo em".
+    pub muted: Rgb,
+    /// Table rules and column headers.
+    pub rule: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            brand: Rgb([0, 206, 120]),
+            text: Rgb([28, 28, 30]),
+            muted: Rgb([174, 174, 178]),
+            rule: Rgb([199, 199, 204]),
+        }
+    }
+}
+
+/// Font sizes in points.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Typography {
+    /// "Imposto de Renda - Pessoa Física".
+    pub title: u8,
+    /// Document name next to the logo and the "Bens e Direitos" page title.
+    pub heading: u8,
+    /// Block titles and subtitles, asset headings.
+    pub subheading: u8,
+    /// Identification lines such as CNPJ, CPF, agência and conta.
+    pub body: u8,
+    /// Table cells.
+    pub table: u8,
+    /// Fine print such as the Rendimentos Líquidos label, and the running header and footer.
+    pub small: u8,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            title: 16,
+            heading: 14,
+            subheading: 12,
+            body: 12,
+            table: 10,
+            small: 8,
+        }
+    }
+}
+
+/// Distances in millimeters, unless noted otherwise.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spacing {
+    /// Line spacing factor.
+    pub line_spacing: f64,
+    pub page_margins: f64,
+    /// Space below a title or subtitle.
+    pub block_padding: f64,
+    /// Space above and below a framed section.
+    pub section_padding: f64,
+    /// Space below each row of a table.
+    pub row_padding: f64,
+    /// Space below the text of a table cell.
+    pub cell_padding: f64,
+}
+
+impl Spacing {
+    /// Padding below an element, `bottom` millimeters high.
+    pub fn below(bottom: f64) -> Margins {
+        let zero = Mm::from(0);
+        Margins::trbl(zero, zero, Mm::from(bottom), zero)
+    }
+
+    /// Padding above and below an element, `vertical` millimeters high each.
+    pub fn around(vertical: f64) -> Margins {
+        let zero = Mm::from(0);
+        Margins::trbl(Mm::from(vertical), zero, Mm::from(vertical), zero)
+    }
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            line_spacing: 1.25,
+            page_margins: 10.0,
+            block_padding: 2.0,
+            section_padding: 5.0,
+            row_padding: 3.0,
+            cell_padding: 2.0,
+        }
+    }
+}